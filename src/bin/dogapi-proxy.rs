@@ -0,0 +1,85 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! Caching reverse proxy for the Dog API
+//!
+//! Usage: dogapi-proxy [--listen ADDR] [--upstream URL] [--ttl SECONDS] [--sample-local]
+use std::env;
+use std::process;
+use std::sync::Arc;
+use std::time::Duration;
+use dogapi::metrics::PrometheusMetrics;
+use dogapi::server::{ProxyBackend, Server};
+use dogapi::Client;
+
+const USAGE: &str = "Usage: dogapi-proxy [--listen ADDR] [--upstream URL] [--ttl SECONDS] [--sample-local]
+
+Options:
+    --listen ADDR       address to listen on (default: 127.0.0.1:8080)
+    --upstream URL      url of the upstream API (default: https://dog.ceo/api)
+    --ttl SECONDS       how long the breeds and image lists are cached (default: 3600)
+    --sample-local      answer random endpoints from the cached image lists
+
+Metrics are served at /metrics.";
+
+fn fail(message: &str, code: i32) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(code)
+}
+
+fn main() {
+    let mut listen = String::from("127.0.0.1:8080");
+    let mut upstream = String::from("https://dog.ceo/api");
+    let mut ttl = 3600;
+    let mut sample_local = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return
+            },
+            "--sample-local" => sample_local = true,
+            "--listen" | "--upstream" | "--ttl" => {
+                let value = match args.next() {
+                    Some(value) => value,
+                    None => fail(&format!("{} requires a value\n\n{}", arg, USAGE), 2)
+                };
+                match arg.as_str() {
+                    "--listen" => listen = value,
+                    "--upstream" => upstream = value,
+                    _ => ttl = match value.parse() {
+                        Ok(ttl) => ttl,
+                        Err(_) => fail(&format!("invalid value '{}' for --ttl", value), 2)
+                    }
+                }
+            },
+            _ => fail(&format!("unexpected argument '{}'\n\n{}", arg, USAGE), 2)
+        }
+    }
+    let metrics = Arc::new(PrometheusMetrics::new());
+    let client = match Client::builder().base_url(&upstream).metrics(metrics.clone()).build() {
+        Ok(client) => client,
+        Err(err) => fail(&err.to_string(), 1)
+    };
+    let backend = ProxyBackend::new(client)
+        .ttl(Duration::from_secs(ttl))
+        .sample_local(sample_local)
+        .metrics(metrics.clone());
+    eprintln!("Proxying {} on http://{}", upstream, listen);
+    if let Err(err) = Server::new(backend).metrics(metrics).serve(&listen) {
+        fail(&err.to_string(), 1)
+    }
+}
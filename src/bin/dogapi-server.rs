@@ -0,0 +1,74 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! Self-hosted Dog API
+//!
+//! Usage: dogapi-server [--dir DIR] [--listen ADDR] [--public-url URL]
+use std::env;
+use std::process;
+use dogapi::server::Server;
+
+const USAGE: &str = "Usage: dogapi-server [--dir DIR] [--listen ADDR] [--public-url URL]
+
+Options:
+    --dir DIR           directory containing breeds/<breed>-<sub-breed>/<file> (default: .)
+    --listen ADDR       address to listen on (default: 127.0.0.1:8080)
+    --public-url URL    url the image links point at (default: http://<Host header>)";
+
+fn main() {
+    let mut dir = String::from(".");
+    let mut listen = String::from("127.0.0.1:8080");
+    let mut public_url = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return
+            },
+            "--dir" | "--listen" | "--public-url" => match args.next() {
+                Some(value) => value,
+                None => {
+                    eprintln!("error: {} requires a value\n\n{}", arg, USAGE);
+                    process::exit(2)
+                }
+            },
+            _ => {
+                eprintln!("error: unexpected argument '{}'\n\n{}", arg, USAGE);
+                process::exit(2)
+            }
+        };
+        match arg.as_str() {
+            "--dir" => dir = value,
+            "--listen" => listen = value,
+            _ => public_url = Some(value)
+        }
+    }
+    let mut server = match Server::from_dir(&dir) {
+        Ok(server) => server,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1)
+        }
+    };
+    if let Some(url) = public_url {
+        server = server.public_url(&url);
+    }
+    eprintln!("Serving {} on http://{}", dir, listen);
+    if let Err(err) = server.serve(&listen) {
+        eprintln!("error: {}", err);
+        process::exit(1)
+    }
+}
@@ -0,0 +1,235 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! `browse` command of the command-line client, an interactive breed browser
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{self, BufRead, Write};
+use dogapi::BreedPath;
+use dogapi::Client;
+use dogapi::DogAPIError;
+
+const HELP: &str = "Commands:
+    TEXT               search breeds and sub-breeds containing TEXT
+    all                list every breed
+    N | BREED[/SUB]    highlight an entry of the list and preview a random image
+    n, next            highlight the next entry
+    p, prev            highlight the previous entry
+    e, expand [N]      show or hide the sub-breeds of a breed
+    r, refresh         preview another random image of the highlighted entry
+    h, help            print this help
+    q, quit            exit";
+
+/// Renders the preview image, `None` to print its url only
+pub type Preview = Option<Box<dyn Fn(&[u8], &mut dyn Write) -> Result<(), DogAPIError>>>;
+
+/// State of the breed browser
+pub struct Browser<'a> {
+    client: &'a Client,
+    catalog: BTreeMap<String, Vec<String>>,
+    filter: String,
+    expanded: BTreeSet<String>,
+    entries: Vec<BreedPath>,
+    highlighted: Option<usize>,
+    counts: HashMap<BreedPath, usize>,
+    preview: Preview
+}
+
+impl Browser<'_> {
+    /// Loads the breeds list
+    pub fn new(client: &Client, preview: Preview) -> Result<Browser<'_>, DogAPIError> {
+        let catalog = client.breeds_list()?.into_iter()
+            .map(|(breed, sub_breeds)| {
+                let mut sub_breeds = sub_breeds.unwrap_or_default();
+                sub_breeds.sort();
+                (breed, sub_breeds)
+            })
+            .collect();
+        let mut browser = Browser {
+            client,
+            catalog,
+            filter: String::new(),
+            expanded: BTreeSet::new(),
+            entries: vec![],
+            highlighted: None,
+            counts: HashMap::new(),
+            preview
+        };
+        browser.update();
+        Ok(browser)
+    }
+
+    /// Reads commands from `input` until `quit` or the end of the input
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, out: &mut W) -> io::Result<()> {
+        writeln!(out, "{} breeds, type a name to search or `help`", self.catalog.len())?;
+        self.list(out)?;
+        let mut lines = input.lines();
+        loop {
+            write!(out, "dogapi> ")?;
+            out.flush()?;
+            let line = match lines.next() {
+                Some(line) => line?,
+                None => return writeln!(out)
+            };
+            let line = line.trim();
+            let (command, argument) = match line.split_once(' ') {
+                Some((command, argument)) => (command, argument.trim()),
+                None => (line, "")
+            };
+            match command {
+                "" => {},
+                "q" | "quit" | "exit" => return Ok(()),
+                "h" | "help" => writeln!(out, "{}", HELP)?,
+                "all" => self.search("", out)?,
+                "n" | "next" => self.step(1, out)?,
+                "p" | "prev" => self.step(-1, out)?,
+                "e" | "expand" => self.expand(argument, out)?,
+                "r" | "refresh" => match self.highlighted {
+                    Some(index) => self.highlight(index, out)?,
+                    None => writeln!(out, "Nothing highlighted")?
+                },
+                _ => match self.find(line) {
+                    Some(index) => self.highlight(index, out)?,
+                    None if line.parse::<usize>().is_ok() => writeln!(out, "No such entry '{}'", line)?,
+                    None => self.search(line, out)?
+                }
+            }
+        }
+    }
+
+    fn update(&mut self) {
+        let filter = self.filter.to_lowercase();
+        let highlighted = self.highlighted.map(|index| self.entries[index].clone());
+        self.entries.clear();
+        for (breed, sub_breeds) in self.catalog.iter() {
+            let matching: Vec<&String> = sub_breeds.iter().filter(|sub_breed| sub_breed.contains(&filter)).collect();
+            if !breed.contains(&filter) && matching.is_empty() {
+                continue
+            }
+            self.entries.push(BreedPath::new(breed, None));
+            if self.expanded.contains(breed) || (!filter.is_empty() && !matching.is_empty()) {
+                for sub_breed in if breed.contains(&filter) { sub_breeds.iter().collect() } else { matching } {
+                    self.entries.push(BreedPath::new(breed, Some(sub_breed)));
+                }
+            }
+        }
+        self.highlighted = highlighted.and_then(|path| self.entries.iter().position(|entry| *entry == path));
+    }
+
+    fn find(&self, target: &str) -> Option<usize> {
+        if let Ok(number) = target.parse::<usize>() {
+            return number.checked_sub(1).filter(|index| *index < self.entries.len())
+        }
+        let path: BreedPath = target.parse().ok()?;
+        self.entries.iter().position(|entry| *entry == path)
+    }
+
+    fn search<W: Write>(&mut self, filter: &str, out: &mut W) -> io::Result<()> {
+        self.filter = filter.to_string();
+        self.update();
+        self.list(out)
+    }
+
+    fn list<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        if self.entries.is_empty() {
+            return writeln!(out, "No breed matches '{}'", self.filter)
+        }
+        let width = self.entries.len().to_string().len();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let marker = if self.highlighted == Some(index) { '>' } else { ' ' };
+            match entry.sub_breed {
+                Some(ref sub_breed) => writeln!(out, "{} {:>width$}     {}", marker, index + 1, sub_breed, width = width)?,
+                None => {
+                    let sub_breeds = self.catalog.get(&entry.breed).map(Vec::len).unwrap_or_default();
+                    let expanded = self.entries.get(index + 1).is_some_and(|next| next.breed == entry.breed);
+                    match (sub_breeds, expanded) {
+                        (0, _) => writeln!(out, "{} {:>width$}   {}", marker, index + 1, entry.breed, width = width)?,
+                        (count, true) => writeln!(out, "{} {:>width$} - {} ({} sub-breeds)", marker, index + 1, entry.breed, count, width = width)?,
+                        (count, false) => writeln!(out, "{} {:>width$} + {} ({} sub-breeds)", marker, index + 1, entry.breed, count, width = width)?
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn expand<W: Write>(&mut self, target: &str, out: &mut W) -> io::Result<()> {
+        let index = match target {
+            "" => self.highlighted,
+            target => self.find(target)
+        };
+        let breed = match index {
+            Some(index) => self.entries[index].breed.clone(),
+            None => return writeln!(out, "No such entry '{}'", target)
+        };
+        if !self.expanded.remove(&breed) {
+            self.expanded.insert(breed);
+        }
+        self.update();
+        self.list(out)
+    }
+
+    fn step<W: Write>(&mut self, offset: isize, out: &mut W) -> io::Result<()> {
+        if self.entries.is_empty() {
+            return writeln!(out, "No breed matches '{}'", self.filter)
+        }
+        let index = match self.highlighted {
+            Some(index) => (index as isize + offset).rem_euclid(self.entries.len() as isize) as usize,
+            None => 0
+        };
+        self.highlight(index, out)
+    }
+
+    /// Highlights an entry and prints its image count and a random image
+    fn highlight<W: Write>(&mut self, index: usize, out: &mut W) -> io::Result<()> {
+        self.highlighted = Some(index);
+        let path = self.entries[index].clone();
+        writeln!(out, "{}", path)?;
+        if path.sub_breed.is_none() {
+            if let Some(sub_breeds) = self.catalog.get(&path.breed).filter(|sub_breeds| !sub_breeds.is_empty()) {
+                writeln!(out, "  sub-breeds: {}", sub_breeds.join(", "))?;
+            }
+        }
+        match self.count(&path) {
+            Ok(count) => writeln!(out, "  images: {}", count)?,
+            Err(err) => writeln!(out, "  images: {}", err)?
+        }
+        let url = match path.sub_breed {
+            Some(ref sub_breed) => self.client.random_image_by_sub_breed(&path.breed, sub_breed),
+            None => self.client.random_image_by_breed(&path.breed)
+        };
+        let url = match url {
+            Ok(url) => url,
+            Err(err) => return writeln!(out, "  preview: {}", err)
+        };
+        if let Some(ref preview) = self.preview {
+            if let Err(err) = self.client.download_image(&url).and_then(|bytes| preview(&bytes, out)) {
+                writeln!(out, "  preview: {}", err)?;
+            }
+        }
+        writeln!(out, "  {}", url)
+    }
+
+    fn count(&mut self, path: &BreedPath) -> Result<usize, DogAPIError> {
+        if let Some(count) = self.counts.get(path) {
+            return Ok(*count)
+        }
+        let count = match path.sub_breed {
+            Some(ref sub_breed) => self.client.images_by_sub_breed(&path.breed, sub_breed)?.len(),
+            None => self.client.images_by_breed(&path.breed)?.len()
+        };
+        self.counts.insert(path.clone(), count);
+        Ok(count)
+    }
+}
@@ -0,0 +1,125 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! Shell completions of the command-line client
+//!
+//! The scripts complete breed names by calling `dogapi __complete`, which answers from a
+//! copy of `breeds_list()` cached for a day in `$XDG_CACHE_HOME/dogapi/breeds.json`, or
+//! `breeds-<hash>.json` for another `--base-url`.
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use dogapi::Client;
+
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+const DEFAULT_BASE_URL: &str = "https://dog.ceo/api";
+/// Timeout of the refresh, a completion must not hang the shell when the API is unreachable
+pub const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Returns the completion script of a shell
+pub fn script(shell: &str) -> Option<&'static str> {
+    match shell {
+        "bash" => Some(include_str!("completions/dogapi.bash")),
+        "zsh" => Some(include_str!("completions/_dogapi")),
+        "fish" => Some(include_str!("completions/dogapi.fish")),
+        _ => None
+    }
+}
+
+/// Returns the candidates of a breed argument
+///
+/// * `base_url` `--base-url` of the command line, the cache is kept per API
+/// * `kind` `breeds`, `paths` (breeds and `breed/sub-breed`) or `sub-breeds`
+/// * `breed` breed of the `sub-breeds` candidates
+pub fn words(client: &Client, base_url: Option<&str>, kind: &str, breed: Option<&str>) -> Vec<String> {
+    let breeds = catalog(client, base_url);
+    match kind {
+        "breeds" => breeds.into_keys().collect(),
+        "paths" => {
+            let mut paths = vec![];
+            for (breed, sub_breeds) in breeds {
+                for sub_breed in sub_breeds.iter() {
+                    paths.push(format!("{}/{}", breed, sub_breed));
+                }
+                paths.push(breed);
+            }
+            paths.sort();
+            paths
+        },
+        "sub-breeds" => match breed.and_then(|breed| breeds.get(breed)) {
+            Some(sub_breeds) => sub_breeds.clone(),
+            None => vec![]
+        },
+        _ => vec![]
+    }
+}
+
+/// Returns the cached catalog, refreshing it when it is missing or older than a day
+///
+/// A stale copy is used when the refresh fails.
+fn catalog(client: &Client, base_url: Option<&str>) -> BTreeMap<String, Vec<String>> {
+    let file = cache_file(base_url);
+    let cached: Option<BTreeMap<String, Vec<String>>> = file.as_ref()
+        .and_then(|file| fs::read(file).ok())
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+    let fresh = file.as_ref()
+        .and_then(|file| fs::metadata(file).ok())
+        .and_then(|metadata| metadata.modified().ok())
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < MAX_AGE);
+    if let (Some(cached), true) = (cached.as_ref(), fresh) {
+        return cached.clone()
+    }
+    match client.breeds_list() {
+        Ok(breeds) => {
+            let breeds: BTreeMap<String, Vec<String>> = breeds.into_iter()
+                .map(|(breed, sub_breeds)| (breed, sub_breeds.unwrap_or_default()))
+                .collect();
+            if let (Some(file), Ok(json)) = (file, serde_json::to_vec(&breeds)) {
+                let part = file.with_extension("json.part");
+                let _ = file.parent().map(fs::create_dir_all);
+                if fs::write(&part, json).is_ok() {
+                    let _ = fs::rename(&part, &file);
+                }
+            }
+            breeds
+        },
+        Err(_) => cached.unwrap_or_default()
+    }
+}
+
+fn cache_file(base_url: Option<&str>) -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".cache")
+    };
+    let name = match base_url.map(|url| url.trim().trim_end_matches('/')) {
+        Some(url) if url != DEFAULT_BASE_URL => format!("breeds-{:016x}.json", fnv1a(url.as_bytes())),
+        _ => String::from("breeds.json")
+    };
+    Some(dir.join("dogapi").join(name))
+}
+
+/// FNV-1a hash, stable across builds unlike the `std` hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
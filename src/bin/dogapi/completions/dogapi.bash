@@ -0,0 +1,68 @@
+# bash completion for dogapi
+# eval "$(dogapi completions bash)"
+
+# --base-url of the command line, from COMP_LINE since COMP_WORDS splits urls on ':'
+_dogapi_base_url() {
+    if [[ $COMP_LINE =~ --base-url[=[:space:]]+([^[:space:]]+) ]]; then
+        echo "--base-url=${BASH_REMATCH[1]}"
+    fi
+}
+
+_dogapi_complete() {
+    dogapi $(_dogapi_base_url) __complete "$@" 2>/dev/null
+}
+
+_dogapi_breed() {
+    local i
+    for ((i = 1; i < COMP_CWORD - 1; i++)); do
+        case "${COMP_WORDS[i]}" in
+            --breed|images|sub-breeds) echo "${COMP_WORDS[i + 1]}"; return ;;
+        esac
+    done
+}
+
+_dogapi() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD - 1]}"
+    local command="" i
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        case "${COMP_WORDS[i]}" in
+            --base-url|--output|--breed|--sub|--count|--dir|--concurrency|--limit|--protocol|--width|--title)
+                # COMP_WORDS splits `--option=value` and urls on '=' and ':'
+                ((i++))
+                [[ ${COMP_WORDS[i]} == = ]] && ((i++))
+                while [[ ${COMP_WORDS[i + 1]} == : ]]; do ((i += 2)); done ;;
+            -*) ;;
+            *) command="${COMP_WORDS[i]}"; break ;;
+        esac
+    done
+    case "$prev" in
+        --output) COMPREPLY=($(compgen -W "plain json ndjson csv tree dot mermaid markdown" -- "$cur")); return ;;
+        --breed) COMPREPLY=($(compgen -W "$(_dogapi_complete breeds)" -- "$cur")); return ;;
+        --sub) COMPREPLY=($(compgen -W "$(_dogapi_complete sub-breeds "$(_dogapi_breed)")" -- "$cur")); return ;;
+        --protocol) COMPREPLY=($(compgen -W "auto kitty sixel truecolor ascii" -- "$cur")); return ;;
+        --dir) COMPREPLY=($(compgen -d -- "$cur")); return ;;
+        --base-url|--count|--concurrency|--limit|--width|--title) return ;;
+    esac
+    if [[ $cur == -* ]]; then
+        local options="--base-url --output --help"
+        case "$command" in
+            random) options="$options --breed --sub --count" ;;
+            images) options="$options --sub" ;;
+            download) options="$options --dir --concurrency --limit --random" ;;
+            show) options="$options --breed --sub --protocol --width" ;;
+            gallery) options="$options --dir --title --limit" ;;
+            browse) options="$options --protocol --width" ;;
+        esac
+        COMPREPLY=($(compgen -W "$options" -- "$cur"))
+        return
+    fi
+    case "$command" in
+        "") COMPREPLY=($(compgen -W "random images breeds sub-breeds download show gallery browse completions" -- "$cur")) ;;
+        images|sub-breeds) COMPREPLY=($(compgen -W "$(_dogapi_complete breeds)" -- "$cur")) ;;
+        download|gallery) COMPREPLY=($(compgen -W "$(_dogapi_complete paths)" -- "$cur")) ;;
+        completions) COMPREPLY=($(compgen -W "bash zsh fish" -- "$cur")) ;;
+    esac
+}
+
+complete -F _dogapi dogapi
@@ -0,0 +1,57 @@
+# fish completion for dogapi
+# dogapi completions fish > ~/.config/fish/completions/dogapi.fish
+
+function __dogapi_breed
+    set -l tokens (commandline -opc)
+    for i in (seq (count $tokens))
+        if contains -- $tokens[$i] --breed images
+            set -l next (math $i + 1)
+            if test $next -le (count $tokens)
+                echo $tokens[$next]
+                return
+            end
+        end
+    end
+end
+
+function __dogapi_complete
+    set -l tokens (commandline -opc)
+    set -l base_url
+    for i in (seq (count $tokens))
+        if test "$tokens[$i]" = --base-url; and test $i -lt (count $tokens)
+            set base_url --base-url=$tokens[(math $i + 1)]
+        else if string match -q -- '--base-url=*' $tokens[$i]
+            set base_url $tokens[$i]
+        end
+    end
+    dogapi $base_url __complete $argv 2>/dev/null
+end
+
+set -l commands random images breeds sub-breeds download show gallery browse completions
+
+complete -c dogapi -f
+complete -c dogapi -l base-url -x -d 'url of the API'
+complete -c dogapi -l output -x -a 'plain json ndjson csv tree dot mermaid markdown' -d 'output format'
+complete -c dogapi -s h -l help -d 'print the help'
+complete -c dogapi -n "not __fish_seen_subcommand_from $commands" -a random -d 'random images, from a breed or sub-breed'
+complete -c dogapi -n "not __fish_seen_subcommand_from $commands" -a images -d 'every image of a breed or sub-breed'
+complete -c dogapi -n "not __fish_seen_subcommand_from $commands" -a breeds -d 'every breed and its sub-breeds'
+complete -c dogapi -n "not __fish_seen_subcommand_from $commands" -a sub-breeds -d 'sub-breeds of a breed'
+complete -c dogapi -n "not __fish_seen_subcommand_from $commands" -a download -d 'save the images of a breed'
+complete -c dogapi -n "not __fish_seen_subcommand_from $commands" -a show -d 'render an image in the terminal'
+complete -c dogapi -n "not __fish_seen_subcommand_from $commands" -a gallery -d 'write a static HTML gallery'
+complete -c dogapi -n "not __fish_seen_subcommand_from $commands" -a browse -d 'search breeds interactively'
+complete -c dogapi -n "not __fish_seen_subcommand_from $commands" -a completions -d 'print a completion script'
+complete -c dogapi -n '__fish_seen_subcommand_from random show' -l breed -x -a '(__dogapi_complete breeds)' -d 'breed'
+complete -c dogapi -n '__fish_seen_subcommand_from random images show' -l sub -x -a '(__dogapi_complete sub-breeds (__dogapi_breed))' -d 'sub-breed'
+complete -c dogapi -n '__fish_seen_subcommand_from random' -l count -x -d 'number of images'
+complete -c dogapi -n '__fish_seen_subcommand_from images sub-breeds' -a '(__dogapi_complete breeds)'
+complete -c dogapi -n '__fish_seen_subcommand_from download gallery' -a '(__dogapi_complete paths)'
+complete -c dogapi -n '__fish_seen_subcommand_from download gallery' -l dir -x -a '(__fish_complete_directories)' -d 'output directory'
+complete -c dogapi -n '__fish_seen_subcommand_from download' -l concurrency -x -d 'parallel downloads'
+complete -c dogapi -n '__fish_seen_subcommand_from download gallery' -l limit -x -d 'maximum number of images'
+complete -c dogapi -n '__fish_seen_subcommand_from gallery' -l title -x -d 'title of the index'
+complete -c dogapi -n '__fish_seen_subcommand_from download' -l random -d 'download random images'
+complete -c dogapi -n '__fish_seen_subcommand_from show browse' -l protocol -x -a 'auto kitty sixel truecolor ascii' -d 'graphics protocol'
+complete -c dogapi -n '__fish_seen_subcommand_from show browse' -l width -x -d 'width in columns'
+complete -c dogapi -n '__fish_seen_subcommand_from completions' -a 'bash zsh fish'
@@ -0,0 +1,189 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! `download` command of the command-line client
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use dogapi::BreedPath;
+use dogapi::Client;
+use dogapi::DogAPIError;
+use dogapi::mirror::{image_path, write_atomic};
+
+/// Result of the download of one image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Saved by this run
+    Downloaded,
+    /// Already present in the directory
+    Skipped,
+    Failed
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Status::Downloaded => "downloaded",
+            Status::Skipped => "skipped",
+            Status::Failed => "failed"
+        }
+    }
+}
+
+/// Downloaded, skipped or failed image
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub url: String,
+    pub path: String,
+    pub status: Status,
+    pub error: Option<DogAPIError>
+}
+
+/// Selection of the images to download
+pub struct Download {
+    pub path: BreedPath,
+    pub dir: PathBuf,
+    pub concurrency: usize,
+    pub limit: Option<usize>,
+    pub random: bool
+}
+
+impl Download {
+    /// Lists the images and downloads the missing ones
+    ///
+    /// Images are saved as `dir/<breed>[-<sub-breed>]/<file>`, the ones already present are skipped, so an interrupted run is resumed by running it again.
+    pub fn run(&self, client: &Client) -> Result<Vec<Record>, DogAPIError> {
+        let mut urls = self.urls(client)?;
+        urls.sort();
+        urls.dedup();
+        if let Some(limit) = self.limit {
+            urls.truncate(limit);
+        }
+        let progress = Progress::new(urls.len());
+        let queue = Mutex::new(urls.into_iter());
+        let records = Mutex::new(vec![]);
+        thread::scope(|scope| {
+            for _ in 0..self.concurrency.max(1) {
+                scope.spawn(|| loop {
+                    let next = match queue.lock() {
+                        Ok(mut queue) => queue.next(),
+                        Err(_) => None
+                    };
+                    let url = match next {
+                        Some(url) => url,
+                        None => break
+                    };
+                    let record = self.fetch(client, url);
+                    progress.tick(record.status == Status::Failed);
+                    match records.lock() {
+                        Ok(mut records) => records.push(record),
+                        Err(poisoned) => poisoned.into_inner().push(record)
+                    }
+                });
+            }
+        });
+        progress.finish();
+        let mut records = match records.into_inner() {
+            Ok(records) => records,
+            Err(poisoned) => poisoned.into_inner()
+        };
+        records.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(records)
+    }
+
+    fn urls(&self, client: &Client) -> Result<Vec<String>, DogAPIError> {
+        let path = &self.path;
+        match (self.random, path.sub_breed.as_deref()) {
+            (true, Some(sub_breed)) => client.multiple_random_images_by_sub_breed(&path.breed, sub_breed, self.limit.unwrap_or(10) as i64),
+            (true, None) => client.multiple_random_images_by_breed(&path.breed, self.limit.unwrap_or(10) as i64),
+            (false, Some(sub_breed)) => client.images_by_sub_breed(&path.breed, sub_breed),
+            (false, None) => client.images_by_breed(&path.breed)
+        }
+    }
+
+    fn fetch(&self, client: &Client, url: String) -> Record {
+        let relative = match image_path(&url) {
+            Ok(relative) => relative,
+            Err(err) => return Record { url, path: String::new(), status: Status::Failed, error: Some(err) }
+        };
+        // The last two segments are the breed directory and the file
+        let mut segments = relative.rsplit('/');
+        let file = match (segments.next(), segments.next()) {
+            (Some(name), Some(breed)) => self.dir.join(breed).join(name),
+            _ => self.dir.join(&relative)
+        };
+        let path = file.display().to_string();
+        if let Ok(metadata) = fs::metadata(&file) {
+            if metadata.is_file() && metadata.len() > 0 {
+                return Record { url, path, status: Status::Skipped, error: None }
+            }
+        }
+        match client.download_image(&url).and_then(|bytes| write_atomic(&file, &bytes)) {
+            Ok(_) => Record { url, path, status: Status::Downloaded, error: None },
+            Err(err) => Record { url, path, status: Status::Failed, error: Some(err) }
+        }
+    }
+}
+
+/// Progress bar drawn on stderr when it is a terminal
+struct Progress {
+    total: usize,
+    counts: Mutex<(usize, usize)>,
+    enabled: bool
+}
+
+impl Progress {
+    fn new(total: usize) -> Progress {
+        let progress = Progress {
+            total,
+            counts: Mutex::new((0, 0)),
+            enabled: io::stderr().is_terminal()
+        };
+        progress.draw(0, 0);
+        progress
+    }
+
+    fn tick(&self, failed: bool) {
+        let mut counts = match self.counts.lock() {
+            Ok(counts) => counts,
+            Err(poisoned) => poisoned.into_inner()
+        };
+        counts.0 += 1;
+        if failed {
+            counts.1 += 1;
+        }
+        self.draw(counts.0, counts.1);
+    }
+
+    fn draw(&self, done: usize, failed: usize) {
+        if !self.enabled {
+            return
+        }
+        let width = 30;
+        let filled = (done * width).checked_div(self.total).unwrap_or(width);
+        eprint!("\r[{}{}] {}/{}", "#".repeat(filled), "-".repeat(width - filled), done, self.total);
+        if failed > 0 {
+            eprint!(" ({} failed)", failed);
+        }
+    }
+
+    fn finish(&self) {
+        if self.enabled {
+            eprintln!();
+        }
+    }
+}
@@ -0,0 +1,402 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! Command-line client for the Dog API
+//!
+//! Usage: dogapi [--base-url URL] [--output FORMAT] <COMMAND> [ARGS]
+mod browse;
+mod completions;
+mod download;
+mod output;
+#[cfg(feature = "image")]
+mod show;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::io::{self, ErrorKind, Write};
+use std::path::PathBuf;
+use std::process;
+use dogapi::BreedPath;
+use dogapi::Client;
+use dogapi::DogAPIError;
+use dogapi::gallery::Gallery;
+use dogapi::names::Aliases;
+use download::Download;
+use output::{Format, Output};
+
+const USAGE: &str = "Usage: dogapi [--base-url URL] [--output FORMAT] <COMMAND> [ARGS]
+
+Commands:
+    random [--breed BREED] [--sub SUB] [--count N]    random images, from a breed or sub-breed
+    images BREED [--sub SUB]                          every image of a breed or sub-breed
+    breeds                                            every breed and its sub-breeds
+    sub-breeds BREED                                  sub-breeds of a breed
+    download BREED[/SUB] [--dir DIR] [--concurrency N] [--limit N] [--random]
+                                                      save the images of a breed, skipping the ones already in DIR
+    show [URL] [--breed BREED] [--sub SUB] [--protocol PROTOCOL] [--width COLUMNS]
+                                                      render an image in the terminal with kitty, sixel,
+                                                      truecolor or ascii (default: detected), needs the image feature
+    gallery BREED[/SUB]... [--dir DIR] [--title TITLE] [--limit N]
+                                                      write a static HTML gallery of the breeds
+    browse [--protocol PROTOCOL] [--width COLUMNS]   search breeds interactively, previewing random images
+    completions SHELL                                 print the bash, zsh or fish completion script
+
+Options:
+    --base-url URL      url of the API (default: https://dog.ceo/api)
+    --output FORMAT     plain, json, ndjson, csv or tree (default: plain),
+                        dot, mermaid or markdown for breeds and sub-breeds
+    -h, --help          print this help
+
+Breeds are given as paths, e.g. hound/afghan, or as names and aliases, e.g. 'Afghan Hound' or lab.
+
+Exit codes:
+    0    success
+    1    the request failed, e.g. connection error or invalid response
+    2    invalid arguments
+    3    breed or image not found
+    4    the API answered with another error
+    5    the response exceeded the size limit";
+
+const GLOBAL_OPTIONS: [&str; 2] = ["--base-url", "--output"];
+
+type Command = fn(&Client, &Args) -> Result<Output, DogAPIError>;
+
+fn fail(message: &str, code: i32) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(code)
+}
+
+fn usage(message: &str) -> ! {
+    fail(&format!("{}\n\n{}", message, USAGE), 2)
+}
+
+fn exit_code(err: &DogAPIError) -> i32 {
+    match *err {
+        DogAPIError::Error(_) => 1,
+        DogAPIError::ResponseTooLarge(_) => 5,
+        DogAPIError::Api { .. } if err.code().or(err.status()) == Some(404) => 3,
+        DogAPIError::Api { .. } => 4,
+        _ => 1
+    }
+}
+
+/// Positional arguments, `--option VALUE` pairs and `--flag`s of a command
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+    flags: Vec<String>
+}
+
+impl Args {
+    /// Parses `args`, accepting the global options, `options` and `flags`
+    fn parse(args: Vec<String>, options: &[&str], flags: &[&str]) -> Args {
+        let mut parsed = Args {
+            positional: vec![],
+            options: vec![],
+            flags: vec![]
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                println!("{}", USAGE);
+                process::exit(0)
+            }
+            if !arg.starts_with("--") {
+                parsed.positional.push(arg);
+                continue
+            }
+            if flags.contains(&arg.as_str()) {
+                parsed.flags.push(arg);
+                continue
+            }
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (arg, None)
+            };
+            if !GLOBAL_OPTIONS.contains(&name.as_str()) && !options.contains(&name.as_str()) {
+                usage(&format!("unexpected argument '{}'", name))
+            }
+            let value = match value.or_else(|| args.next()) {
+                Some(value) => value,
+                None => usage(&format!("{} requires a value", name))
+            };
+            parsed.options.push((name, value));
+        }
+        parsed
+    }
+
+    /// Returns `true` if a flag was passed
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    /// Returns the last value of an option
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|(option, _)| option == name).map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the value of an option parsed as a number
+    fn number<T: std::str::FromStr>(&self, name: &str) -> Option<T> {
+        self.value(name).map(|value| match value.parse() {
+            Ok(number) => number,
+            Err(_) => usage(&format!("invalid value '{}' for {}", value, name))
+        })
+    }
+
+    /// Returns the positional arguments, failing unless there are exactly `count`
+    fn positional(&self, count: usize, names: &str) -> &[String] {
+        if self.positional.len() != count {
+            usage(&format!("expected {}", names))
+        }
+        &self.positional
+    }
+}
+
+/// Resolves a breed name, e.g. `afghan hound`, `lab` or `hound/afghan`, fetching the breeds list
+/// unless it is an alias or a breed path
+fn resolve(client: &Client, name: &str, sub: Option<&str>) -> Result<BreedPath, DogAPIError> {
+    let name = match sub {
+        Some(sub) => format!("{}/{}", name, sub),
+        None => name.to_string()
+    };
+    let aliases = Aliases::new();
+    if let Some(path) = aliases.resolve(&name) {
+        return Ok(path)
+    }
+    match aliases.resolve_in(&name, &client.breeds_list()?) {
+        Some(path) => Ok(path),
+        None => Err(DogAPIError::Api {
+            message: format!("No breed matches '{}'", name),
+            code: Some(404),
+            status: None
+        })
+    }
+}
+
+fn random(client: &Client, args: &Args) -> Result<Output, DogAPIError> {
+    args.positional(0, "no arguments");
+    let count: Option<i64> = args.number("--count");
+    let path = match (args.value("--breed"), args.value("--sub")) {
+        (Some(breed), sub) => Some(resolve(client, breed, sub)?),
+        (None, Some(_)) => usage("--sub requires --breed"),
+        (None, None) => None
+    };
+    let urls = match (path, count) {
+        (None, None) => client.random_image().map(|url| vec![url]),
+        (None, Some(count)) => match i8::try_from(count) {
+            Ok(count) => client.multiple_random_images(count),
+            Err(_) => usage(&format!("invalid value '{}' for --count", count))
+        },
+        (Some(path), count) => match (path.sub_breed.as_deref(), count) {
+            (None, None) => client.random_image_by_breed(&path.breed).map(|url| vec![url]),
+            (None, Some(count)) => client.multiple_random_images_by_breed(&path.breed, count),
+            (Some(sub), None) => client.random_image_by_sub_breed(&path.breed, sub).map(|url| vec![url]),
+            (Some(sub), Some(count)) => client.multiple_random_images_by_sub_breed(&path.breed, sub, count)
+        }
+    };
+    Ok(Output::Images(urls?))
+}
+
+fn images(client: &Client, args: &Args) -> Result<Output, DogAPIError> {
+    let path = resolve(client, &args.positional(1, "a breed")[0], args.value("--sub"))?;
+    let urls = match path.sub_breed {
+        Some(ref sub) => client.images_by_sub_breed(&path.breed, sub),
+        None => client.images_by_breed(&path.breed)
+    };
+    Ok(Output::Images(urls?))
+}
+
+fn breeds(client: &Client, args: &Args) -> Result<Output, DogAPIError> {
+    args.positional(0, "no arguments");
+    let catalog = client.breeds_list()?;
+    let breeds: BTreeMap<String, Vec<String>> = catalog.into_iter()
+        .map(|(breed, sub_breeds)| (breed, sub_breeds.unwrap_or_default()))
+        .collect();
+    Ok(Output::Breeds(breeds))
+}
+
+fn sub_breeds(client: &Client, args: &Args) -> Result<Output, DogAPIError> {
+    let path = resolve(client, &args.positional(1, "a breed")[0], None)?;
+    Ok(Output::SubBreeds(path.breed.clone(), client.sub_breeds_list(&path.breed)?.unwrap_or_default()))
+}
+
+fn download(client: &Client, args: &Args) -> Result<Output, DogAPIError> {
+    let path = resolve(client, &args.positional(1, "a breed or breed/sub-breed")[0], None)?;
+    let download = Download {
+        path,
+        dir: PathBuf::from(args.value("--dir").unwrap_or(".")),
+        concurrency: args.number("--concurrency").unwrap_or(4),
+        limit: args.number("--limit"),
+        random: args.flag("--random")
+    };
+    Ok(Output::Download(download.run(client)?))
+}
+
+#[cfg(feature = "image")]
+fn show(client: &Client, args: &Args) -> Result<Output, DogAPIError> {
+    let url = match (args.positional.as_slice(), args.value("--breed"), args.value("--sub")) {
+        ([url], None, None) => url.to_string(),
+        ([], None, None) => client.random_image()?,
+        ([], Some(breed), sub) => match resolve(client, breed, sub)? {
+            BreedPath { breed, sub_breed: Some(sub) } => client.random_image_by_sub_breed(&breed, &sub)?,
+            BreedPath { breed, sub_breed: None } => client.random_image_by_breed(&breed)?
+        },
+        ([], None, Some(_)) => usage("--sub requires --breed"),
+        _ => usage("expected a url or --breed")
+    };
+    let protocol = protocol(args);
+    let columns = match args.number("--width") {
+        Some(columns) => columns,
+        None => env::var("COLUMNS").ok().and_then(|columns| columns.parse().ok()).unwrap_or(80)
+    };
+    let bytes = client.download_image(&url)?;
+    show::render(&bytes, protocol, columns, &mut io::stdout().lock())?;
+    Ok(Output::Images(vec![url]))
+}
+
+#[cfg(not(feature = "image"))]
+fn show(_client: &Client, _args: &Args) -> Result<Output, DogAPIError> {
+    fail("dogapi was built without image support, install it with `cargo install dogapi --features image`", 1)
+}
+
+#[cfg(feature = "image")]
+fn protocol(args: &Args) -> show::Protocol {
+    match args.value("--protocol").unwrap_or("auto") {
+        "auto" => show::Protocol::detect(),
+        protocol => match protocol.parse::<show::Protocol>() {
+            Ok(protocol) => protocol,
+            Err(err) => usage(&err)
+        }
+    }
+}
+
+#[cfg(feature = "image")]
+fn preview(args: &Args) -> browse::Preview {
+    let protocol = protocol(args);
+    let columns = args.number("--width").unwrap_or(40);
+    Some(Box::new(move |bytes, mut out| show::render(bytes, protocol, columns, &mut out)))
+}
+
+#[cfg(not(feature = "image"))]
+fn preview(_args: &Args) -> browse::Preview {
+    None
+}
+
+fn browse(client: &Client, args: &Args) -> Result<Output, DogAPIError> {
+    args.positional(0, "no arguments");
+    let mut browser = browse::Browser::new(client, preview(args))?;
+    if let Err(err) = browser.run(io::stdin().lock(), &mut io::stdout().lock()) {
+        fail(&format!("Something went wrong while reading the input: {}", err), 1)
+    }
+    Ok(Output::Lines(vec![]))
+}
+
+fn gallery(client: &Client, args: &Args) -> Result<Output, DogAPIError> {
+    if args.positional.is_empty() {
+        usage("expected at least one breed or breed/sub-breed")
+    }
+    let mut paths: Vec<BreedPath> = vec![];
+    for name in args.positional.iter() {
+        paths.push(resolve(client, name, None)?);
+    }
+    let dir = PathBuf::from(args.value("--dir").unwrap_or("."));
+    let mut gallery = Gallery::new(client.clone(), &dir);
+    if let Some(title) = args.value("--title") {
+        gallery = gallery.title(title);
+    }
+    if let Some(limit) = args.number("--limit") {
+        gallery = gallery.limit(limit);
+    }
+    let pages = gallery.generate(&paths)?;
+    for page in pages.iter() {
+        for url in page.rejected.iter() {
+            eprintln!("warning: {}: skipped image url that is not http or https: {}", page.path, url);
+        }
+    }
+    let mut files = vec![dir.join("index.html").display().to_string()];
+    files.extend(pages.iter().map(|page| dir.join(&page.file).display().to_string()));
+    Ok(Output::Lines(files))
+}
+
+fn completions(_client: &Client, args: &Args) -> Result<Output, DogAPIError> {
+    let shell = &args.positional(1, "a shell")[0];
+    match completions::script(shell) {
+        Some(script) => Ok(Output::Lines(script.lines().map(String::from).collect())),
+        None => usage(&format!("unsupported shell '{}', expected bash, zsh or fish", shell))
+    }
+}
+
+fn complete(client: &Client, args: &Args) -> Result<Output, DogAPIError> {
+    let kind = args.positional.first().map(String::as_str).unwrap_or_default();
+    Ok(Output::Lines(completions::words(client, args.value("--base-url"), kind, args.positional.get(1).map(String::as_str))))
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mut position = 0;
+    while position < args.len() && args[position].starts_with('-') {
+        position += if GLOBAL_OPTIONS.contains(&args[position].as_str()) { 2 } else { 1 };
+    }
+    if position >= args.len() {
+        Args::parse(args, &[], &[]);
+        usage("a command is required")
+    }
+    let command = args.remove(position);
+    let (run, options, flags): (Command, &[&str], &[&str]) = match command.as_str() {
+        "random" => (random, &["--breed", "--sub", "--count"], &[]),
+        "images" => (images, &["--sub"], &[]),
+        "breeds" => (breeds, &[], &[]),
+        "sub-breeds" => (sub_breeds, &[], &[]),
+        "download" => (download, &["--dir", "--concurrency", "--limit"], &["--random"]),
+        "show" => (show, &["--breed", "--sub", "--protocol", "--width"], &[]),
+        "gallery" => (gallery, &["--dir", "--title", "--limit"], &[]),
+        "browse" => (browse, &["--protocol", "--width"], &[]),
+        "completions" => (completions, &[], &[]),
+        "__complete" => (complete, &[], &[]),
+        _ => usage(&format!("unknown command '{}'", command))
+    };
+    let args = Args::parse(args, options, flags);
+    let format: Format = match args.value("--output").unwrap_or("plain").parse() {
+        Ok(format) => format,
+        Err(err) => usage(&err)
+    };
+    if format.is_hierarchy() && command != "breeds" && command != "sub-breeds" {
+        usage(&format!("--output {} is only supported by breeds and sub-breeds", args.value("--output").unwrap_or_default()))
+    }
+    let mut builder = Client::builder();
+    if let Some(url) = args.value("--base-url") {
+        builder = builder.base_url(url);
+    }
+    if command == "__complete" {
+        builder = builder.timeout(completions::TIMEOUT);
+    }
+    let client = match builder.build() {
+        Ok(client) => client,
+        Err(err) => fail(&err.to_string(), exit_code(&err))
+    };
+    let output = match run(&client, &args) {
+        Ok(output) => output,
+        Err(err) => fail(&err.to_string(), exit_code(&err))
+    };
+    let mut stdout = io::stdout().lock();
+    match output.write(format, &mut stdout).and_then(|_| stdout.flush()) {
+        Ok(_) => {},
+        Err(err) if err.kind() == ErrorKind::BrokenPipe => {},
+        Err(err) => fail(&format!("Something went wrong while writing the output: {}", err), 1)
+    }
+    if let Some(err) = output.error() {
+        process::exit(exit_code(err))
+    }
+}
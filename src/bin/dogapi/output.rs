@@ -0,0 +1,287 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! Output formats of the command-line client
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, ErrorKind, Write};
+use std::str::FromStr;
+use dogapi::BreedPath;
+use dogapi::export;
+use serde_json::{json, Value};
+use crate::download::{Record, Status};
+
+/// Format selected with `--output`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Plain,
+    Json,
+    Ndjson,
+    Csv,
+    Tree,
+    Dot,
+    Mermaid,
+    Markdown
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Format, String> {
+        match format {
+            "plain" => Ok(Format::Plain),
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            "csv" => Ok(Format::Csv),
+            "tree" => Ok(Format::Tree),
+            "dot" => Ok(Format::Dot),
+            "mermaid" => Ok(Format::Mermaid),
+            "markdown" => Ok(Format::Markdown),
+            _ => Err(format!("invalid value '{}' for --output, expected plain, json, ndjson, csv, tree, dot, mermaid or markdown", format))
+        }
+    }
+}
+
+impl Format {
+    /// Returns `true` for the formats of the breed hierarchy only
+    pub fn is_hierarchy(&self) -> bool {
+        matches!(*self, Format::Dot | Format::Mermaid | Format::Markdown)
+    }
+}
+
+/// Result of a command
+pub enum Output {
+    /// Image urls
+    Images(Vec<String>),
+    /// Breeds and their sub-breeds
+    Breeds(BTreeMap<String, Vec<String>>),
+    /// Sub-breeds of a breed
+    SubBreeds(String, Vec<String>),
+    /// Images of a download
+    Download(Vec<Record>),
+    /// Lines of text
+    Lines(Vec<String>)
+}
+
+impl Output {
+    /// Returns the first error of a download
+    pub fn error(&self) -> Option<&dogapi::DogAPIError> {
+        match *self {
+            Output::Download(ref records) => records.iter().find_map(|record| record.error.as_ref()),
+            _ => None
+        }
+    }
+
+    /// Writes the output in `format`
+    pub fn write<W: Write>(&self, format: Format, out: &mut W) -> io::Result<()> {
+        match format {
+            Format::Plain => {
+                for line in self.lines() {
+                    writeln!(out, "{}", line)?;
+                }
+                Ok(())
+            },
+            Format::Json => writeln!(out, "{}", serde_json::to_string_pretty(&self.json())?),
+            Format::Ndjson => {
+                for record in self.records() {
+                    writeln!(out, "{}", record)?;
+                }
+                Ok(())
+            },
+            Format::Csv => {
+                let (header, rows) = self.rows();
+                writeln!(out, "{}", header.join(","))?;
+                for row in rows {
+                    writeln!(out, "{}", row.iter().map(|field| csv_field(field)).collect::<Vec<String>>().join(","))?;
+                }
+                Ok(())
+            },
+            Format::Tree => {
+                for (node, children) in self.tree() {
+                    writeln!(out, "{}", node)?;
+                    for (index, child) in children.iter().enumerate() {
+                        writeln!(out, "{} {}", if index + 1 == children.len() { "└──" } else { "├──" }, child)?;
+                    }
+                }
+                Ok(())
+            },
+            Format::Dot | Format::Mermaid | Format::Markdown => {
+                let catalog = match self.catalog() {
+                    Some(catalog) => catalog,
+                    None => return Err(io::Error::new(ErrorKind::InvalidInput, "only breeds and sub-breeds can be written as a hierarchy"))
+                };
+                let text = match format {
+                    Format::Dot => export::dot(&catalog),
+                    Format::Mermaid => export::mermaid(&catalog),
+                    _ => export::markdown(&catalog)
+                };
+                out.write_all(text.as_bytes())
+            }
+        }
+    }
+
+    /// Returns the breeds in the shape of `breeds_list`
+    fn catalog(&self) -> Option<HashMap<String, Option<Vec<String>>>> {
+        let catalog = |breed: &String, sub_breeds: &Vec<String>| (breed.to_string(), if sub_breeds.is_empty() { None } else { Some(sub_breeds.clone()) });
+        match *self {
+            Output::Breeds(ref breeds) => Some(breeds.iter().map(|(breed, sub_breeds)| catalog(breed, sub_breeds)).collect()),
+            Output::SubBreeds(ref breed, ref sub_breeds) => Some(HashMap::from([catalog(breed, sub_breeds)])),
+            _ => None
+        }
+    }
+
+    fn lines(&self) -> Vec<String> {
+        match *self {
+            Output::Images(ref urls) => urls.clone(),
+            Output::Breeds(ref breeds) => {
+                let mut lines = vec![];
+                for (breed, sub_breeds) in breeds {
+                    lines.push(breed.to_string());
+                    for sub_breed in sub_breeds {
+                        lines.push(format!("{}/{}", breed, sub_breed));
+                    }
+                }
+                lines
+            },
+            Output::SubBreeds(_, ref sub_breeds) => sub_breeds.clone(),
+            Output::Lines(ref lines) => lines.clone(),
+            Output::Download(ref records) => {
+                let mut lines = vec![];
+                for record in records {
+                    if let Some(ref error) = record.error {
+                        lines.push(format!("failed {}: {}", record.url, error));
+                    }
+                }
+                let count = |status| records.iter().filter(|record| record.status == status).count();
+                lines.push(format!("{} downloaded, {} skipped, {} failed", count(Status::Downloaded), count(Status::Skipped), count(Status::Failed)));
+                lines
+            }
+        }
+    }
+
+    fn json(&self) -> Value {
+        match *self {
+            Output::Images(_) => Value::Array(self.records()),
+            Output::Breeds(ref breeds) => json!(breeds),
+            Output::SubBreeds(_, ref sub_breeds) => json!(sub_breeds),
+            Output::Lines(ref lines) => json!(lines),
+            Output::Download(ref records) => {
+                let count = |status| records.iter().filter(|record| record.status == status).count();
+                json!({
+                    "downloaded": count(Status::Downloaded),
+                    "skipped": count(Status::Skipped),
+                    "failed": count(Status::Failed),
+                    "images": self.records()
+                })
+            }
+        }
+    }
+
+    fn records(&self) -> Vec<Value> {
+        match *self {
+            Output::Images(ref urls) => urls.iter().map(|url| {
+                let path = BreedPath::from_image_url(url);
+                json!({
+                    "url": url,
+                    "breed": path.as_ref().map(|path| &path.breed),
+                    "sub_breed": path.as_ref().and_then(|path| path.sub_breed.as_ref())
+                })
+            }).collect(),
+            Output::Breeds(ref breeds) => breeds.iter().map(|(breed, sub_breeds)| json!({
+                "breed": breed,
+                "sub_breeds": sub_breeds
+            })).collect(),
+            Output::SubBreeds(ref breed, ref sub_breeds) => sub_breeds.iter().map(|sub_breed| json!({
+                "breed": breed,
+                "sub_breed": sub_breed
+            })).collect(),
+            Output::Lines(ref lines) => lines.iter().map(|line| json!(line)).collect(),
+            Output::Download(ref records) => records.iter().map(|record| json!({
+                "url": record.url,
+                "path": record.path,
+                "status": record.status.as_str(),
+                "error": record.error.as_ref().map(|error| error.to_string())
+            })).collect()
+        }
+    }
+
+    fn rows(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        match *self {
+            Output::Images(ref urls) => (vec!["url", "breed", "sub_breed"], urls.iter().map(|url| {
+                match BreedPath::from_image_url(url) {
+                    Some(path) => vec![url.to_string(), path.breed, path.sub_breed.unwrap_or_default()],
+                    None => vec![url.to_string(), String::new(), String::new()]
+                }
+            }).collect()),
+            Output::Breeds(ref breeds) => {
+                let mut rows = vec![];
+                for (breed, sub_breeds) in breeds {
+                    if sub_breeds.is_empty() {
+                        rows.push(vec![breed.to_string(), String::new()]);
+                    }
+                    for sub_breed in sub_breeds {
+                        rows.push(vec![breed.to_string(), sub_breed.to_string()]);
+                    }
+                }
+                (vec!["breed", "sub_breed"], rows)
+            },
+            Output::SubBreeds(ref breed, ref sub_breeds) => (vec!["breed", "sub_breed"], sub_breeds.iter().map(|sub_breed| {
+                vec![breed.to_string(), sub_breed.to_string()]
+            }).collect()),
+            Output::Lines(ref lines) => (vec!["line"], lines.iter().map(|line| vec![line.to_string()]).collect()),
+            Output::Download(ref records) => (vec!["url", "path", "status", "error"], records.iter().map(|record| {
+                vec![
+                    record.url.to_string(),
+                    record.path.to_string(),
+                    record.status.as_str().to_string(),
+                    record.error.as_ref().map(|error| error.to_string()).unwrap_or_default()
+                ]
+            }).collect())
+        }
+    }
+
+    fn tree(&self) -> Vec<(String, Vec<String>)> {
+        match *self {
+            Output::Images(ref urls) => {
+                let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+                for url in urls {
+                    let path = match BreedPath::from_image_url(url) {
+                        Some(path) => path.to_string(),
+                        None => String::from("unknown")
+                    };
+                    groups.entry(path).or_default().push(url.to_string());
+                }
+                groups.into_iter().collect()
+            },
+            Output::Breeds(ref breeds) => breeds.iter().map(|(breed, sub_breeds)| (breed.to_string(), sub_breeds.clone())).collect(),
+            Output::SubBreeds(ref breed, ref sub_breeds) => vec![(breed.to_string(), sub_breeds.clone())],
+            Output::Lines(ref lines) => lines.iter().map(|line| (line.to_string(), vec![])).collect(),
+            Output::Download(ref records) => [Status::Downloaded, Status::Skipped, Status::Failed].iter().filter_map(|status| {
+                let children: Vec<String> = records.iter().filter(|record| record.status == *status).map(|record| match record.error {
+                    Some(ref error) => format!("{}: {}", record.url, error),
+                    None => record.path.to_string()
+                }).collect();
+                if children.is_empty() { None } else { Some((status.as_str().to_string(), children)) }
+            }).collect()
+        }
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,219 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! `show` command of the command-line client, renders an image in the terminal
+use std::env;
+use std::io::{self, Write};
+use std::str::FromStr;
+use dogapi::DogAPIError;
+use image::imageops::FilterType;
+use image::RgbImage;
+
+/// Approximate size of a terminal cell in pixels, used to size kitty and Sixel images
+const CELL_WIDTH: u32 = 10;
+
+const ASCII_RAMP: &[u8] = b" .:-=+*#%@";
+
+/// How the image is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// kitty graphics protocol
+    Kitty,
+    /// Sixel graphics
+    Sixel,
+    /// 24-bit colored half-block characters
+    Truecolor,
+    /// Characters of increasing density
+    Ascii
+}
+
+impl FromStr for Protocol {
+    type Err = String;
+
+    fn from_str(protocol: &str) -> Result<Protocol, String> {
+        match protocol {
+            "kitty" => Ok(Protocol::Kitty),
+            "sixel" => Ok(Protocol::Sixel),
+            "truecolor" => Ok(Protocol::Truecolor),
+            "ascii" => Ok(Protocol::Ascii),
+            _ => Err(format!("invalid value '{}' for --protocol, expected auto, kitty, sixel, truecolor or ascii", protocol))
+        }
+    }
+}
+
+impl Protocol {
+    /// Guesses the best protocol of the terminal from its environment variables
+    pub fn detect() -> Protocol {
+        let var = |name| env::var(name).unwrap_or_default().to_lowercase();
+        let term = var("TERM");
+        let program = var("TERM_PROGRAM");
+        if env::var_os("KITTY_WINDOW_ID").is_some() || term.contains("kitty") || program == "wezterm" || program == "ghostty" {
+            Protocol::Kitty
+        } else if term.contains("sixel") || term.starts_with("mlterm") || term.starts_with("foot") || term == "yaft-256color" {
+            Protocol::Sixel
+        } else if matches!(var("COLORTERM").as_str(), "truecolor" | "24bit") {
+            Protocol::Truecolor
+        } else {
+            Protocol::Ascii
+        }
+    }
+}
+
+/// Decodes an image and draws it `columns` cells wide
+pub fn render<W: Write>(bytes: &[u8], protocol: Protocol, columns: u32, out: &mut W) -> Result<(), DogAPIError> {
+    let image = match image::load_from_memory(bytes) {
+        Ok(image) => image,
+        Err(err) => return Err(DogAPIError::Error(format!("Something went wrong while decoding the image: {}", err)))
+    };
+    let columns = columns.max(1);
+    let (width, height) = (image.width().max(1), image.height().max(1));
+    let scaled = |width_px: u32, aspect: u32| {
+        let height_px = (height as u64 * width_px as u64 / width as u64 / aspect as u64).max(1) as u32;
+        image.resize_exact(width_px, height_px, FilterType::Triangle).to_rgb8()
+    };
+    let result = match protocol {
+        Protocol::Kitty => kitty(&scaled((columns * CELL_WIDTH).min(width), 1), columns, out),
+        Protocol::Sixel => sixel(&scaled((columns * CELL_WIDTH).min(width), 1), out),
+        Protocol::Truecolor => {
+            let mut pixels = scaled(columns, 1);
+            if pixels.height() % 2 == 1 {
+                pixels = image::imageops::resize(&pixels, columns, pixels.height() + 1, FilterType::Triangle);
+            }
+            truecolor(&pixels, out)
+        },
+        Protocol::Ascii => ascii(&scaled(columns, 2), out)
+    };
+    match result.and_then(|_| out.flush()) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(DogAPIError::Error(format!("Something went wrong while writing the image: {}", err)))
+    }
+}
+
+/// Sends the pixels with the kitty graphics protocol, scaled by the terminal to `columns` cells
+fn kitty<W: Write>(pixels: &RgbImage, columns: u32, out: &mut W) -> io::Result<()> {
+    let data = base64(pixels.as_raw());
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(4096).collect();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = if index + 1 < chunks.len() { 1 } else { 0 };
+        if index == 0 {
+            write!(out, "\x1b_Gf=24,a=T,s={},v={},c={},m={};", pixels.width(), pixels.height(), columns, more)?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        }
+        out.write_all(chunk)?;
+        write!(out, "\x1b\\")?;
+    }
+    writeln!(out)
+}
+
+/// Writes the pixels as Sixel graphics with a 6x6x6 color cube palette
+fn sixel<W: Write>(pixels: &RgbImage, out: &mut W) -> io::Result<()> {
+    let (width, height) = (pixels.width(), pixels.height());
+    let level = |value: u8| (value as u32 * 5 + 127) / 255;
+    let indices: Vec<u32> = pixels.pixels().map(|pixel| level(pixel[0]) * 36 + level(pixel[1]) * 6 + level(pixel[2])).collect();
+    write!(out, "\x1bPq\"1;1;{};{}", width, height)?;
+    for index in 0..216 {
+        let percent = |level: u32| level * 100 / 5;
+        write!(out, "#{};2;{};{};{}", index, percent(index / 36), percent(index / 6 % 6), percent(index % 6))?;
+    }
+    for band in (0..height).step_by(6) {
+        let rows = (height - band).min(6);
+        let mut colors: Vec<u32> = (band..band + rows)
+            .flat_map(|y| indices[(y * width) as usize..((y + 1) * width) as usize].iter().copied())
+            .collect();
+        colors.sort_unstable();
+        colors.dedup();
+        for (position, color) in colors.iter().enumerate() {
+            write!(out, "#{}", color)?;
+            let mut run: Option<(u8, usize)> = None;
+            for x in 0..width {
+                let mut bits = 0;
+                for row in 0..rows {
+                    if indices[((band + row) * width + x) as usize] == *color {
+                        bits |= 1 << row;
+                    }
+                }
+                let symbol = b'?' + bits;
+                run = match run {
+                    Some((previous, count)) if previous == symbol => Some((previous, count + 1)),
+                    Some((previous, count)) => {
+                        write_run(previous, count, out)?;
+                        Some((symbol, 1))
+                    },
+                    None => Some((symbol, 1))
+                };
+            }
+            if let Some((symbol, count)) = run {
+                write_run(symbol, count, out)?;
+            }
+            if position + 1 < colors.len() {
+                write!(out, "$")?;
+            }
+        }
+        write!(out, "-")?;
+    }
+    writeln!(out, "\x1b\\")
+}
+
+fn write_run<W: Write>(symbol: u8, count: usize, out: &mut W) -> io::Result<()> {
+    if count > 3 {
+        write!(out, "!{}{}", count, symbol as char)
+    } else {
+        out.write_all(&vec![symbol; count])
+    }
+}
+
+/// Draws two pixels per cell with the upper half block, foreground on top and background below
+fn truecolor<W: Write>(pixels: &RgbImage, out: &mut W) -> io::Result<()> {
+    for y in (0..pixels.height()).step_by(2) {
+        for x in 0..pixels.width() {
+            let top = pixels.get_pixel(x, y);
+            let bottom = pixels.get_pixel(x, (y + 1).min(pixels.height() - 1));
+            write!(out, "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}", top[0], top[1], top[2], bottom[0], bottom[1], bottom[2])?;
+        }
+        writeln!(out, "\x1b[0m")?;
+    }
+    Ok(())
+}
+
+/// Draws one pixel per cell with characters of increasing density
+fn ascii<W: Write>(pixels: &RgbImage, out: &mut W) -> io::Result<()> {
+    for y in 0..pixels.height() {
+        let line: Vec<u8> = (0..pixels.width()).map(|x| {
+            let pixel = pixels.get_pixel(x, y);
+            let luma = (pixel[0] as u32 * 299 + pixel[1] as u32 * 587 + pixel[2] as u32 * 114) / 1000;
+            ASCII_RAMP[(luma as usize * (ASCII_RAMP.len() - 1)) / 255]
+        }).collect();
+        out.write_all(&line)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let value = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(value >> (18 - index * 6) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,83 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::error::DogAPIError;
+
+/// Breed and optional sub-breed, written `hound` or `hound/afghan`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BreedPath {
+    /// Breed name
+    pub breed: String,
+    /// Sub-breed name
+    pub sub_breed: Option<String>
+}
+
+impl BreedPath {
+    /// Creates a path from a breed and an optional sub-breed
+    pub fn new(breed: &str, sub_breed: Option<&str>) -> BreedPath {
+        BreedPath {
+            breed: breed.trim().to_string(),
+            sub_breed: sub_breed.map(|sub_breed| sub_breed.trim().to_string())
+        }
+    }
+
+    /// Parses the breed of an image url
+    ///
+    /// * `url` image url, e.g. `https://images.dog.ceo/breeds/hound-afghan/n02088094_1003.jpg`
+    ///
+    /// Returns `None` if the url has no `breeds/<breed>[-<sub-breed>]/<file>` segments
+    pub fn from_image_url(url: &str) -> Option<BreedPath> {
+        let path = match url.split_once("://") {
+            Some((_, rest)) => rest,
+            None => url
+        };
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').collect();
+        let position = segments.iter().rposition(|segment| *segment == "breeds")?;
+        if segments.len() != position + 3 || segments[position + 1].is_empty() || segments[position + 2].is_empty() {
+            return None
+        }
+        Some(match segments[position + 1].split_once('-') {
+            Some((breed, sub_breed)) => BreedPath::new(breed, Some(sub_breed)),
+            None => BreedPath::new(segments[position + 1], None)
+        })
+    }
+}
+
+impl FromStr for BreedPath {
+    type Err = DogAPIError;
+
+    fn from_str(path: &str) -> Result<BreedPath, DogAPIError> {
+        let (breed, sub_breed) = match path.trim().split_once('/') {
+            Some((breed, sub_breed)) => (breed, Some(sub_breed)),
+            None => (path, None)
+        };
+        if breed.trim().is_empty() || sub_breed.is_some_and(|sub_breed| sub_breed.trim().is_empty() || sub_breed.contains('/')) {
+            return Err(DogAPIError::Error(format!("Invalid breed '{}', expected <breed> or <breed>/<sub-breed>", path)))
+        }
+        Ok(BreedPath::new(breed, sub_breed))
+    }
+}
+
+impl Display for BreedPath {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self.sub_breed {
+            Some(ref sub_breed) => write!(f, "{}/{}", self.breed, sub_breed),
+            None => write!(f, "{}", self.breed)
+        }
+    }
+}
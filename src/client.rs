@@ -0,0 +1,459 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::env;
+use std::io::Read;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::error::DogAPIError;
+use crate::middleware::Chain;
+use crate::middleware::Middleware;
+use crate::middleware::Request;
+use crate::middleware::Response;
+use crate::metrics::Metrics;
+use crate::metrics::Outcome;
+use crate::metrics::Recorder;
+use crate::parse::ParseMode;
+use crate::parse::ParseWarning;
+use crate::parse::Parsing;
+use crate::singleflight::Group;
+use crate::stream::ImageIter;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderName;
+use reqwest::header::HeaderValue;
+use reqwest::NoProxy;
+use reqwest::Proxy;
+#[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "rustls-tls-native-roots"))]
+use reqwest::Certificate;
+
+/// Default maximum size of the JSON responses
+pub(crate) const MAX_RESPONSE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Dog API client
+///
+/// Holds the HTTP connection pool and the configuration used by every endpoint.
+/// Cloning a client is cheap and shares the connection pool.
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::blocking::Client,
+    base_url: String,
+    middleware: Chain,
+    metrics: Recorder,
+    flights: Option<Arc<Group<Result<Response, DogAPIError>>>>,
+    max_response_size: u64,
+    max_image_size: u64,
+    parsing: Parsing
+}
+
+/// Builder for a [`Client`]
+#[derive(Debug)]
+pub struct ClientBuilder {
+    http: reqwest::blocking::ClientBuilder,
+    base_url: String,
+    middleware: Chain,
+    metrics: Recorder,
+    single_flight: bool,
+    max_response_size: u64,
+    max_image_size: u64,
+    parsing: Parsing,
+    error: Option<DogAPIError>
+}
+
+impl Client {
+    /// Creates a client with the default configuration
+    ///
+    /// *NOTE* ~ Panics if the TLS backend cannot be initialized, use [`Client::builder`] to handle that error
+    pub fn new() -> Client {
+        ClientBuilder::new().build().expect("Client::new()")
+    }
+
+    /// Creates a [`ClientBuilder`] to configure a client
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    pub(crate) fn parsing(&self) -> &Parsing {
+        &self.parsing
+    }
+
+    fn send(&self, request: Request, limit: u64) -> Result<Response, DogAPIError> {
+        match self.http.get(&request.url).headers(request.headers).send() {
+            Ok(response) => {
+                if response.content_length().unwrap_or(0) > limit {
+                    return Err(DogAPIError::ResponseTooLarge(limit))
+                }
+                let status = response.status().as_u16();
+                let headers = response.headers().clone();
+                let mut body = vec![];
+                match response.take(limit + 1).read_to_end(&mut body) {
+                    Ok(_) if body.len() as u64 > limit => Err(DogAPIError::ResponseTooLarge(limit)),
+                    Ok(_) => Ok(Response {
+                        url: request.url,
+                        status,
+                        headers,
+                        body
+                    }),
+                    Err(err) => Err(DogAPIError::Error(format!("Something went wrong while reading bytes: {}", err)))
+                }
+            },
+            Err(err) => Err(DogAPIError::Error(err.to_string()))
+        }
+    }
+
+    fn execute(&self, name: &str, url: &str, limit: u64) -> Result<Response, DogAPIError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("dogapi.http", url).entered();
+        let start = Instant::now();
+        let mut request = Request {
+            url: url.to_string(),
+            headers: HeaderMap::new()
+        };
+        let result = self.middleware.on_request(&mut request)
+            .and_then(|_| self.send(request, limit))
+            .and_then(|mut response| {
+                self.middleware.on_response(&mut response)?;
+                Ok(response)
+            });
+        match result {
+            Ok(ref response) => {
+                let outcome = if (200..300).contains(&response.status) { Outcome::Success } else { Outcome::HttpError };
+                self.metrics.record_request(name, outcome, start.elapsed());
+                self.metrics.record_bytes(name, response.body.len() as u64);
+            },
+            Err(_) => self.metrics.record_request(name, Outcome::TransportError, start.elapsed())
+        }
+        #[cfg(feature = "tracing")]
+        match result {
+            Ok(ref response) => tracing::debug!(status = response.status, bytes = response.body.len(), duration_ms = start.elapsed().as_millis() as u64, "request completed"),
+            Err(ref err) => tracing::warn!(error = %err, duration_ms = start.elapsed().as_millis() as u64, "request failed")
+        }
+        result
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub(crate) fn get_request(&self, name: &str, endpoint: &str) -> Result<Response, DogAPIError> {
+        self.execute(name, &format!("{}/{}", self.base_url, endpoint), self.max_response_size)
+    }
+
+    /// Same as `get_request` for deterministic endpoints, concurrent identical calls share one request
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub(crate) fn get_shared(&self, name: &str, endpoint: &str) -> Result<Response, DogAPIError> {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        match self.flights {
+            Some(ref flights) => {
+                let (result, shared) = flights.run(&url, || self.execute(name, &url, self.max_response_size));
+                if shared {
+                    self.metrics.record_coalesced(name);
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("shared an in-flight request");
+                }
+                result
+            },
+            None => self.execute(name, &url, self.max_response_size)
+        }
+    }
+
+    /// Same as `get_request` for image lists, the body is decoded while it is received
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub(crate) fn get_stream(&self, name: &'static str, endpoint: &str) -> Result<ImageIter, DogAPIError> {
+        let start = Instant::now();
+        let mut request = Request {
+            url: format!("{}/{}", self.base_url, endpoint),
+            headers: HeaderMap::new()
+        };
+        self.middleware.on_request(&mut request)?;
+        let response = match self.http.get(&request.url).headers(request.headers).send() {
+            Ok(response) => response,
+            Err(err) => {
+                self.metrics.record_request(name, Outcome::TransportError, start.elapsed());
+                return Err(DogAPIError::Error(err.to_string()))
+            }
+        };
+        let status = response.status().as_u16();
+        self.metrics.record_request(name, if (200..300).contains(&status) { Outcome::Success } else { Outcome::HttpError }, start.elapsed());
+        let mut head = Response {
+            url: request.url,
+            status,
+            headers: response.headers().clone(),
+            body: vec![]
+        };
+        self.middleware.on_response(&mut head)?;
+        if response.content_length().unwrap_or(0) > self.max_response_size {
+            return Err(DogAPIError::ResponseTooLarge(self.max_response_size))
+        }
+        Ok(ImageIter::new(Box::new(response), name, status, self.max_response_size, self.metrics.clone(), self.parsing.clone()))
+    }
+
+    /// DOWNLOAD AN IMAGE
+    ///
+    /// * `url` image url, e.g. returned by [`Client::random_image`]
+    ///
+    /// Returns the image bytes
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn download_image(&self, url: &str) -> Result<Vec<u8>, DogAPIError> {
+        let response = self.execute("download_image", url.trim(), self.max_image_size)?;
+        if !(200..300).contains(&response.status) {
+            return Err(DogAPIError::Api {
+                message: format!("Something went wrong while downloading the image: {}", response.status),
+                code: None,
+                status: Some(response.status)
+            })
+        }
+        Ok(response.body)
+    }
+}
+
+impl Default for Client {
+    fn default() -> Client {
+        Client::new()
+    }
+}
+
+impl ClientBuilder {
+    /// Creates a builder with the default configuration
+    pub fn new() -> ClientBuilder {
+        ClientBuilder {
+            http: reqwest::blocking::Client::builder()
+                .user_agent(concat!("dogapi/", env!("CARGO_PKG_VERSION"))),
+            base_url: String::from("https://dog.ceo/api"),
+            middleware: Chain::default(),
+            metrics: Recorder::default(),
+            single_flight: true,
+            max_response_size: MAX_RESPONSE_SIZE,
+            max_image_size: 64 * 1024 * 1024,
+            parsing: Parsing::default(),
+            error: None
+        }
+    }
+
+    /// Sets the url of the API
+    ///
+    /// * `url` API url, defaults to `https://dog.ceo/api`, e.g. `http://127.0.0.1:8080/api` for `dogapi-server`
+    pub fn base_url(mut self, url: &str) -> ClientBuilder {
+        self.base_url = url.trim().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Sets the timeout of each request, from connecting until the body is read
+    ///
+    /// * `timeout` timeout, defaults to 30 seconds
+    pub fn timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.http = self.http.timeout(timeout);
+        self
+    }
+
+    /// Routes every request through a proxy
+    ///
+    /// * `proxy` HTTP, HTTPS or SOCKS proxy (SOCKS requires the `socks` feature)
+    ///
+    /// *NOTE* ~ Adding a proxy disables the automatic use of the proxy environment variables
+    pub fn proxy(mut self, proxy: Proxy) -> ClientBuilder {
+        self.http = self.http.proxy(proxy);
+        self
+    }
+
+    /// Routes requests through the proxies of the environment
+    ///
+    /// Reads `HTTPS_PROXY`, `HTTP_PROXY` and `ALL_PROXY` (or their lowercase variants)
+    /// and excludes the hosts listed in `NO_PROXY`
+    pub fn env_proxy(mut self) -> ClientBuilder {
+        for (upper, lower) in [("HTTPS_PROXY", "https_proxy"), ("HTTP_PROXY", "http_proxy"), ("ALL_PROXY", "all_proxy")] {
+            let url = match env::var(upper).or_else(|_| env::var(lower)) {
+                Ok(url) if !url.trim().is_empty() => url,
+                _ => continue
+            };
+            let proxy = match upper {
+                "HTTPS_PROXY" => Proxy::https(url.trim()),
+                "HTTP_PROXY" => Proxy::http(url.trim()),
+                _ => Proxy::all(url.trim())
+            };
+            match proxy {
+                Ok(proxy) => self.http = self.http.proxy(proxy.no_proxy(NoProxy::from_env())),
+                Err(err) => self.error = Some(DogAPIError::Error(format!("Something went wrong while reading {}: {}", upper, err)))
+            }
+        }
+        self
+    }
+
+    /// Disables every proxy, including the ones of the environment
+    pub fn no_proxy(mut self) -> ClientBuilder {
+        self.http = self.http.no_proxy();
+        self
+    }
+
+    /// Sets the `User-Agent` header
+    ///
+    /// * `user_agent` user agent, defaults to `dogapi/<version>`
+    pub fn user_agent(mut self, user_agent: &str) -> ClientBuilder {
+        match HeaderValue::from_str(user_agent) {
+            Ok(value) => self.http = self.http.user_agent(value),
+            Err(err) => self.error = Some(DogAPIError::Error(format!("Something went wrong while reading the user agent: {}", err)))
+        }
+        self
+    }
+
+    /// Adds a header to every request, including image downloads
+    ///
+    /// * `name` header name
+    /// * `value` header value
+    pub fn header(mut self, name: &str, value: &str) -> ClientBuilder {
+        match (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+            (Ok(name), Ok(value)) => {
+                let mut headers = HeaderMap::new();
+                headers.insert(name, value);
+                self.http = self.http.default_headers(headers);
+            },
+            _ => self.error = Some(DogAPIError::Error(format!("Something went wrong while reading the header: {}", name)))
+        }
+        self
+    }
+
+    /// Adds a trusted root certificate
+    ///
+    /// * `certificate` certificate, e.g. the CA of a TLS-inspecting proxy
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "rustls-tls-native-roots"))]
+    pub fn add_root_certificate(mut self, certificate: Certificate) -> ClientBuilder {
+        self.http = self.http.add_root_certificate(certificate);
+        self
+    }
+
+    /// Controls the use of the built-in root certificates
+    ///
+    /// * `enabled` when `false` only the certificates added with [`ClientBuilder::add_root_certificate`] are trusted
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "rustls-tls-native-roots"))]
+    pub fn tls_built_in_root_certs(mut self, enabled: bool) -> ClientBuilder {
+        self.http = self.http.tls_built_in_root_certs(enabled);
+        self
+    }
+
+    /// Controls the validation of server certificates
+    ///
+    /// *NOTE* ~ Accepting invalid certificates makes the connection vulnerable to interception, prefer [`ClientBuilder::add_root_certificate`]
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "rustls-tls-native-roots"))]
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> ClientBuilder {
+        self.http = self.http.danger_accept_invalid_certs(accept);
+        self
+    }
+
+    /// Uses the native TLS backend (OpenSSL, Schannel or Security Framework)
+    ///
+    /// Only needed when both the `native-tls` and `rustls-tls` features are enabled
+    #[cfg(feature = "native-tls")]
+    pub fn use_native_tls(mut self) -> ClientBuilder {
+        self.http = self.http.use_native_tls();
+        self
+    }
+
+    /// Uses the rustls TLS backend
+    ///
+    /// Only needed when both the `native-tls` and `rustls-tls` features are enabled
+    #[cfg(any(feature = "rustls-tls", feature = "rustls-tls-native-roots"))]
+    pub fn use_rustls_tls(mut self) -> ClientBuilder {
+        self.http = self.http.use_rustls_tls();
+        self
+    }
+
+    /// Adds a middleware to the chain
+    ///
+    /// * `middleware` interceptor run for every request, including image downloads
+    pub fn middleware<M: Middleware + 'static>(mut self, middleware: M) -> ClientBuilder {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Reports requests into a metrics collector
+    ///
+    /// * `metrics` collector, e.g. [`PrometheusMetrics`](crate::metrics::PrometheusMetrics)
+    pub fn metrics<M: Metrics + 'static>(mut self, metrics: Arc<M>) -> ClientBuilder {
+        self.metrics = Recorder::new(metrics);
+        self
+    }
+
+    /// Controls the deduplication of concurrent identical requests
+    ///
+    /// * `enabled` when `true` (the default) concurrent calls of `breeds_list`, `sub_breeds_list`,
+    ///   `images_by_breed` and `images_by_sub_breed` with the same arguments share one request
+    ///
+    /// *NOTE* ~ Calls sharing the request of another call are reported to the metrics collector
+    /// as `dogapi_coalesced_requests_total`
+    pub fn single_flight(mut self, enabled: bool) -> ClientBuilder {
+        self.single_flight = enabled;
+        self
+    }
+
+    /// Sets the maximum size of the JSON responses
+    ///
+    /// * `bytes` maximum body size, defaults to 16 MiB
+    ///
+    /// Larger responses fail with [`DogAPIError::ResponseTooLarge`]
+    pub fn max_response_size(mut self, bytes: u64) -> ClientBuilder {
+        self.max_response_size = bytes;
+        self
+    }
+
+    /// Sets the maximum size of the downloaded images
+    ///
+    /// * `bytes` maximum image size, defaults to 64 MiB
+    ///
+    /// Larger images fail with [`DogAPIError::ResponseTooLarge`]
+    pub fn max_image_size(mut self, bytes: u64) -> ClientBuilder {
+        self.max_image_size = bytes;
+        self
+    }
+
+    /// Sets how malformed entries of a response are handled
+    ///
+    /// * `mode` [`ParseMode::Lenient`] (default) skips them, [`ParseMode::Strict`] fails with an error naming the entry
+    pub fn parse_mode(mut self, mode: ParseMode) -> ClientBuilder {
+        self.parsing.mode = mode;
+        self
+    }
+
+    /// Sets a callback for the entries skipped in lenient mode
+    ///
+    /// * `on_warning` called once per response with skipped entries
+    ///
+    /// *NOTE* ~ Skipped entries are also logged with the `tracing` feature
+    pub fn on_warning<F: Fn(&ParseWarning) + Send + Sync + 'static>(mut self, on_warning: F) -> ClientBuilder {
+        self.parsing.set_on_warning(Arc::new(on_warning));
+        self
+    }
+
+    /// Builds the client
+    pub fn build(self) -> Result<Client, DogAPIError> {
+        if let Some(err) = self.error {
+            return Err(err)
+        }
+        match self.http.build() {
+            Ok(http) => Ok(Client {
+                http,
+                base_url: self.base_url,
+                middleware: self.middleware,
+                metrics: self.metrics,
+                flights: if self.single_flight { Some(Arc::new(Group::new())) } else { None },
+                max_response_size: self.max_response_size,
+                max_image_size: self.max_image_size,
+                parsing: self.parsing
+            }),
+            Err(err) => Err(DogAPIError::Error(format!("Something went wrong while building the client: {}", err)))
+        }
+    }
+}
+
+impl Default for ClientBuilder {
+    fn default() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+}
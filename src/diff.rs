@@ -0,0 +1,186 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! Differences between two breed catalogs
+//!
+//! A catalog is the map returned by [`breeds_list`](crate::breeds_list) or
+//! [`Manifest::catalog`](crate::mirror::Manifest::catalog) for a stored snapshot.
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Display, Formatter};
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+/// Sub-breed changes of a breed present in both catalogs
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BreedChange {
+    /// Breed name
+    pub breed: String,
+    /// Sub-breeds only in the new catalog
+    pub added_sub_breeds: Vec<String>,
+    /// Sub-breeds only in the old catalog
+    pub removed_sub_breeds: Vec<String>
+}
+
+/// Structured diff between two catalogs
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogDiff {
+    /// Breeds only in the new catalog
+    pub added_breeds: Vec<String>,
+    /// Breeds only in the old catalog
+    pub removed_breeds: Vec<String>,
+    /// Sub-breed paths only in the new catalog, e.g. `hound/afghan`
+    pub added_sub_breeds: Vec<String>,
+    /// Sub-breed paths only in the old catalog
+    pub removed_sub_breeds: Vec<String>,
+    /// Breeds present in both catalogs whose sub-breed lists changed
+    pub changed_breeds: Vec<BreedChange>
+}
+
+impl CatalogDiff {
+    /// Compares two catalogs
+    ///
+    /// * `old` previous catalog
+    /// * `new` current catalog
+    pub fn between(old: &HashMap<String, Option<Vec<String>>>, new: &HashMap<String, Option<Vec<String>>>) -> CatalogDiff {
+        let old_breeds: BTreeSet<&String> = old.keys().collect();
+        let new_breeds: BTreeSet<&String> = new.keys().collect();
+        let old_paths = sub_breed_paths(old);
+        let new_paths = sub_breed_paths(new);
+        let mut changed_breeds = vec![];
+        for breed in old_breeds.intersection(&new_breeds) {
+            let old_sub_breeds = sub_breeds(old, breed);
+            let new_sub_breeds = sub_breeds(new, breed);
+            if old_sub_breeds != new_sub_breeds {
+                changed_breeds.push(BreedChange {
+                    breed: breed.to_string(),
+                    added_sub_breeds: new_sub_breeds.difference(&old_sub_breeds).map(|sub_breed| sub_breed.to_string()).collect(),
+                    removed_sub_breeds: old_sub_breeds.difference(&new_sub_breeds).map(|sub_breed| sub_breed.to_string()).collect()
+                });
+            }
+        }
+        CatalogDiff {
+            added_breeds: new_breeds.difference(&old_breeds).map(|breed| breed.to_string()).collect(),
+            removed_breeds: old_breeds.difference(&new_breeds).map(|breed| breed.to_string()).collect(),
+            added_sub_breeds: new_paths.difference(&old_paths).cloned().collect(),
+            removed_sub_breeds: old_paths.difference(&new_paths).cloned().collect(),
+            changed_breeds
+        }
+    }
+
+    /// Returns `true` if both catalogs are the same
+    pub fn is_empty(&self) -> bool {
+        self.added_breeds.is_empty() && self.removed_breeds.is_empty() && self.changed_breeds.is_empty()
+    }
+}
+
+impl Display for CatalogDiff {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "No changes")
+        }
+        for breed in self.added_breeds.iter() {
+            writeln!(f, "+ {}", breed)?;
+        }
+        for breed in self.removed_breeds.iter() {
+            writeln!(f, "- {}", breed)?;
+        }
+        for change in self.changed_breeds.iter() {
+            writeln!(f, "~ {}", change.breed)?;
+            for sub_breed in change.added_sub_breeds.iter() {
+                writeln!(f, "    + {}", sub_breed)?;
+            }
+            for sub_breed in change.removed_sub_breeds.iter() {
+                writeln!(f, "    - {}", sub_breed)?;
+            }
+        }
+        write!(f, "{} breeds added, {} removed, {} changed; {} sub-breeds added, {} removed",
+               self.added_breeds.len(), self.removed_breeds.len(), self.changed_breeds.len(),
+               self.added_sub_breeds.len(), self.removed_sub_breeds.len())
+    }
+}
+
+fn sub_breeds<'a>(catalog: &'a HashMap<String, Option<Vec<String>>>, breed: &str) -> BTreeSet<&'a String> {
+    match catalog.get(breed) {
+        Some(Some(sub_breeds)) => sub_breeds.iter().collect(),
+        _ => BTreeSet::new()
+    }
+}
+
+fn sub_breed_paths(catalog: &HashMap<String, Option<Vec<String>>>) -> BTreeSet<String> {
+    let mut paths = BTreeSet::new();
+    for (breed, sub_breeds) in catalog {
+        if let Some(sub_breeds) = sub_breeds {
+            for sub_breed in sub_breeds {
+                paths.insert(format!("{}/{}", breed, sub_breed));
+            }
+        }
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::catalog;
+
+    #[test]
+    fn same_catalog_is_empty() {
+        let old = catalog(&[("hound", Some(&["afghan", "basset"])), ("pug", None)]);
+        let diff = CatalogDiff::between(&old, &old.clone());
+        assert!(diff.is_empty());
+        assert_eq!(diff, CatalogDiff::default());
+        assert_eq!(diff.to_string(), "No changes");
+    }
+
+    #[test]
+    fn no_sub_breeds_equals_empty_sub_breeds() {
+        let old = catalog(&[("pug", None)]);
+        let new = catalog(&[("pug", Some(&[]))]);
+        assert!(CatalogDiff::between(&old, &new).is_empty());
+        assert!(CatalogDiff::between(&new, &old).is_empty());
+    }
+
+    #[test]
+    fn sub_breed_changes_are_not_empty() {
+        let old = catalog(&[("hound", None)]);
+        let new = catalog(&[("hound", Some(&["afghan"]))]);
+        let diff = CatalogDiff::between(&old, &new);
+        assert!(!diff.is_empty());
+        assert!(diff.added_breeds.is_empty());
+        assert_eq!(diff.added_sub_breeds, vec!["hound/afghan"]);
+        assert_eq!(diff.changed_breeds, vec![BreedChange {
+            breed: "hound".to_string(),
+            added_sub_breeds: vec!["afghan".to_string()],
+            removed_sub_breeds: vec![]
+        }]);
+        let diff = CatalogDiff::between(&new, &old);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.removed_sub_breeds, vec!["hound/afghan"]);
+        assert_eq!(diff.changed_breeds[0].removed_sub_breeds, vec!["afghan"]);
+    }
+
+    #[test]
+    fn added_and_removed_breeds_carry_their_sub_breeds() {
+        let old = catalog(&[("pug", None), ("terrier", Some(&["irish"]))]);
+        let new = catalog(&[("pug", None), ("hound", Some(&["afghan", "basset"]))]);
+        let diff = CatalogDiff::between(&old, &new);
+        assert_eq!(diff.added_breeds, vec!["hound"]);
+        assert_eq!(diff.removed_breeds, vec!["terrier"]);
+        assert_eq!(diff.added_sub_breeds, vec!["hound/afghan", "hound/basset"]);
+        assert_eq!(diff.removed_sub_breeds, vec!["terrier/irish"]);
+        assert!(diff.changed_breeds.is_empty());
+        assert!(diff.to_string().ends_with("1 breeds added, 1 removed, 0 changed; 2 sub-breeds added, 1 removed"));
+    }
+}
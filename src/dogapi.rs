@@ -14,8 +14,12 @@
  * limitations under the License.
  */
 use std::collections::HashMap;
-use std::io::Read;
+use std::sync::OnceLock;
+use crate::client::Client;
 use crate::error::DogAPIError;
+use crate::middleware;
+use crate::parse::Parsing;
+use crate::stream::ImageIter;
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
 use serde_json::Value;
@@ -24,43 +28,262 @@ use serde_json::Value;
 #[serde(rename_all = "camelCase")]
 struct Response {
     message: String,
-    status: String
+    status: String,
+    #[serde(default)]
+    code: Option<u16>
 }
 
-fn get_request(endpoint: &str) -> Result<String, String> {
-    match reqwest::blocking::Client::new().get(format!("https://dog.ceo/api/{}", endpoint))
-        .send() {
-        Ok(mut response) => {
-            let mut body = String::new();
-            match response.read_to_string(&mut body) {
-                Ok(_) => Ok(body),
-                Err(err) => Err(format!("Something went wrong while reading bytes: {}", err))
+fn json_error() -> DogAPIError {
+    DogAPIError::Error(String::from("Something went wrong while reading json"))
+}
+
+fn api_error(message: String, code: Option<u16>, response: &middleware::Response) -> DogAPIError {
+    DogAPIError::Api {
+        message,
+        code,
+        status: Some(response.status)
+    }
+}
+
+fn read_error(err: serde_json::Error, response: &middleware::Response) -> DogAPIError {
+    let message = format!("Something went wrong while reading json: {}", err);
+    if (200..300).contains(&response.status) {
+        DogAPIError::Error(message)
+    } else {
+        api_error(message, None, response)
+    }
+}
+
+fn single(response: &middleware::Response) -> Result<String, DogAPIError> {
+    match serde_json::from_slice(&response.body) {
+        Ok(json) => {
+            let data: Response = json;
+            if data.status != "success" {
+                Err(api_error(data.message, data.code, response))
+            } else {
+                Ok(data.message)
             }
         },
-        Err(err) => Err(err.to_string())
+        Err(err) => Err(read_error(err, response))
     }
 }
 
-/// DISPLAY SINGLE RANDOM IMAGE FROM ALL DOGS COLLECTION
-///
-/// Returns a random dog image
-pub fn random_image() -> Result<String, DogAPIError> {
-    match get_request("breeds/image/random") {
-        Ok(response) => {
-            match serde_json::from_str(&response) {
-                Ok(json) => {
-                    let data: Response = json;
-                    if data.status != "success" {
-                        Err(DogAPIError::Error(data.message))
-                    } else {
-                        Ok(data.message)
+fn message(response: &middleware::Response) -> Result<Value, DogAPIError> {
+    match serde_json::from_slice(&response.body) {
+        Ok(json) => {
+            let mut data: Value = json;
+            let status = match data.get("status").and_then(Value::as_str) {
+                Some(status) => status == "success",
+                None => return Err(json_error())
+            };
+            match data.get_mut("message").map(Value::take) {
+                Some(message) => if status {
+                    Ok(message)
+                } else {
+                    let code = data.get("code").and_then(Value::as_u64).and_then(|code| u16::try_from(code).ok());
+                    match message.as_str() {
+                        Some(err) => Err(api_error(err.to_string(), code, response)),
+                        None => Err(json_error())
                     }
                 },
-                Err(err) => Err(DogAPIError::Error(format!("Something went wrong while reading json: {}", err.to_string())))
+                None => Err(json_error())
             }
         },
-        Err(err) => Err(DogAPIError::Error(String::from(err)))
+        Err(err) => Err(read_error(err, response))
+    }
+}
+
+fn string_array(parsing: &Parsing, endpoint: &str, value: &Value) -> Result<Vec<String>, DogAPIError> {
+    match value.as_array() {
+        Some(array) => {
+            let vector = strings(parsing, "message", array)?;
+            parsing.skipped(endpoint, array.len() - vector.len());
+            Ok(vector)
+        },
+        None => Err(json_error())
+    }
+}
+
+fn strings(parsing: &Parsing, entry: &str, array: &[Value]) -> Result<Vec<String>, DogAPIError> {
+    let mut vector = vec![];
+    for (index, i) in array.iter().enumerate() {
+        match i.as_str() {
+            Some(value) => vector.push(value.to_string()),
+            None => if let Some(err) = parsing.malformed(&format!("{}[{}]", entry, index), &format!("is not a string: {}", i)) {
+                return Err(err)
+            }
+        }
+    }
+    Ok(vector)
+}
+
+fn default_client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(Client::new)
+}
+
+impl Client {
+    /// DISPLAY SINGLE RANDOM IMAGE FROM ALL DOGS COLLECTION
+    ///
+    /// Returns a random dog image
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn random_image(&self) -> Result<String, DogAPIError> {
+        single(&self.get_request("random_image", "breeds/image/random")?)
+    }
+
+    /// DISPLAY MULTIPLE RANDOM IMAGES FROM ALL DOGS COLLECTION
+    ///
+    /// * `images_number` number of images
+    ///
+    /// *NOTE* ~ Max number returned is 50
+    ///
+    /// Return multiple random dog image
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn multiple_random_images(&self, images_number: i8) -> Result<Vec<String>, DogAPIError> {
+        string_array(self.parsing(), "multiple_random_images", &message(&self.get_request("multiple_random_images", &format!("breeds/image/random/{}", images_number))?)?)
+    }
+
+    /// RANDOM IMAGE FROM A BREED COLLECTION
+    ///
+    /// * `breed` breed name
+    ///
+    /// Returns a random dog image from a breed, e.g. hound
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn random_image_by_breed(&self, breed: &str) -> Result<String, DogAPIError> {
+        single(&self.get_request("random_image_by_breed", &format!("breed/{}/images/random", breed.trim()))?)
+    }
+
+    /// MULTIPLE IMAGES FROM A BREED COLLECTION
+    ///
+    /// * `breed` breed name
+    /// * `images_number` number of images
+    ///
+    /// Return multiple random dog image from a breed, e.g. hound
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn multiple_random_images_by_breed(&self, breed: &str, images_number: i64) -> Result<Vec<String>, DogAPIError> {
+        string_array(self.parsing(), "multiple_random_images_by_breed", &message(&self.get_request("multiple_random_images_by_breed", &format!("breed/{}/images/random/{}", breed.trim(), images_number))?)?)
     }
+
+    /// ALL IMAGES FROM A BREED COLLECTION
+    ///
+    /// * `breed` breed name
+    ///
+    /// Returns an array of all the images from a breed, e.g. hound
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn images_by_breed(&self, breed: &str) -> Result<Vec<String>, DogAPIError> {
+        string_array(self.parsing(), "images_by_breed", &message(&self.get_shared("images_by_breed", &format!("breed/{}/images", breed.trim()))?)?)
+    }
+
+    /// STREAM ALL IMAGES FROM A BREED COLLECTION
+    ///
+    /// * `breed` breed name
+    ///
+    /// Returns an iterator over all the images from a breed, decoded while the response is received
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn images_by_breed_iter(&self, breed: &str) -> Result<ImageIter, DogAPIError> {
+        self.get_stream("images_by_breed_iter", &format!("breed/{}/images", breed.trim()))
+    }
+
+    /// SINGLE RANDOM IMAGE FROM A SUB BREED COLLECTION
+    ///
+    /// * `breed` breed name
+    /// * `sub_breed` sub_breed name
+    ///
+    /// Returns a random dog image from a sub-breed, e.g. Afghan Hound
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn random_image_by_sub_breed(&self, breed: &str, sub_breed: &str) -> Result<String, DogAPIError> {
+        single(&self.get_request("random_image_by_sub_breed", &format!("breed/{}/{}/images/random", breed.trim(), sub_breed.trim()))?)
+    }
+
+    /// MULTIPLE IMAGES FROM A SUB-BREED COLLECTION
+    ///
+    /// * `breed` breed name
+    /// * `sub_breed` sub_breed name
+    /// * `images_number` number of images
+    ///
+    /// Return multiple random dog images from a sub-breed, e.g. Afghan Hound
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn multiple_random_images_by_sub_breed(&self, breed: &str, sub_breed: &str, images_number: i64) -> Result<Vec<String>, DogAPIError> {
+        string_array(self.parsing(), "multiple_random_images_by_sub_breed", &message(&self.get_request("multiple_random_images_by_sub_breed", &format!("breed/{}/{}/images/random/{}", breed.trim(), sub_breed.trim(), images_number))?)?)
+    }
+
+    /// LIST ALL SUB-BREED IMAGES
+    ///
+    /// * `breed` breed name
+    /// * `sub_breed` sub_breed name
+    ///
+    /// Returns an array of all the images from the sub-breed
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn images_by_sub_breed(&self, breed: &str, sub_breed: &str) -> Result<Vec<String>, DogAPIError> {
+        string_array(self.parsing(), "images_by_sub_breed", &message(&self.get_shared("images_by_sub_breed", &format!("breed/{}/{}/images", breed.trim(), sub_breed.trim()))?)?)
+    }
+
+    /// STREAM ALL SUB-BREED IMAGES
+    ///
+    /// * `breed` breed name
+    /// * `sub_breed` sub_breed name
+    ///
+    /// Returns an iterator over all the images from the sub-breed, decoded while the response is received
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn images_by_sub_breed_iter(&self, breed: &str, sub_breed: &str) -> Result<ImageIter, DogAPIError> {
+        self.get_stream("images_by_sub_breed_iter", &format!("breed/{}/{}/images", breed.trim(), sub_breed.trim()))
+    }
+
+    /// LIST ALL BREEDS
+    ///
+    /// Returns map of all the breeds as keys and sub-breeds as values if it has
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn breeds_list(&self) -> Result<HashMap<String, Option<Vec<String>>>, DogAPIError> {
+        match message(&self.get_shared("breeds_list", "breeds/list/all")?)?.as_object() {
+            Some(obj) => {
+                let parsing = self.parsing();
+                let mut map = HashMap::new();
+                let mut skipped = 0;
+                for (i, v) in obj {
+                    match v.as_array() {
+                        Some(value) => {
+                            if value.is_empty() {
+                                map.insert(i.to_string(), None);
+                                continue
+                            }
+                            let vector = strings(parsing, &format!("message.{}", i), value)?;
+                            skipped += value.len() - vector.len();
+                            map.insert(i.to_string(), Some(vector));
+                        },
+                        None => match parsing.malformed(&format!("message.{}", i), &format!("is not an array: {}", v)) {
+                            Some(err) => return Err(err),
+                            None => skipped += 1
+                        }
+                    }
+                }
+                parsing.skipped("breeds_list", skipped);
+                Ok(map)
+            },
+            None => Err(json_error())
+        }
+    }
+
+    /// LIST ALL SUB-BREEDS
+    ///
+    /// * `breed` breed name
+    ///
+    /// Returns an array of all the sub-breeds from a breed if it has sub-breeds
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn sub_breeds_list(&self, breed: &str) -> Result<Option<Vec<String>>, DogAPIError> {
+        let vector = string_array(self.parsing(), "sub_breeds_list", &message(&self.get_shared("sub_breeds_list", &format!("breed/{}/list", breed.trim()))?)?)?;
+        if vector.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(vector))
+        }
+    }
+}
+
+/// DISPLAY SINGLE RANDOM IMAGE FROM ALL DOGS COLLECTION
+///
+/// Returns a random dog image
+pub fn random_image() -> Result<String, DogAPIError> {
+    default_client().random_image()
 }
 
 /// DISPLAY MULTIPLE RANDOM IMAGES FROM ALL DOGS COLLECTION
@@ -71,46 +294,7 @@ pub fn random_image() -> Result<String, DogAPIError> {
 ///
 /// Return multiple random dog image
 pub fn multiple_random_images(images_number: i8) -> Result<Vec<String>, DogAPIError> {
-    match get_request(&format!("breeds/image/random/{}", images_number)) {
-        Ok(response) => {
-            match serde_json::from_str(&response) {
-                Ok(json) => {
-                    let data: Value = json;
-                    match data.get("status") {
-                        Some(status) => match data.get("message") {
-                            Some(message) => match status.as_str() {
-                                Some(status_str) => if status_str != "success" {
-                                    let error = match message.as_str() {
-                                        Some(err) => err,
-                                        None => "Something went wrong while reading json"
-                                    };
-                                    Err(DogAPIError::Error(error.to_string()))
-                                } else {
-                                    match message.as_array() {
-                                        Some(array) => {
-                                            let mut vector = vec![];
-                                            for i in array.iter() {
-                                                if let Some(url) = i.as_str() {
-                                                    vector.push(url.to_string());
-                                                }
-                                            }
-                                            Ok(vector)
-                                        },
-                                        None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                                    }
-                                },
-                                None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                            },
-                            None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                        },
-                        None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                    }
-                },
-                Err(err) => Err(DogAPIError::Error(format!("Something went wrong while reading json: {}", err.to_string())))
-            }
-        },
-        Err(err) => Err(DogAPIError::Error(String::from(err)))
-    }
+    default_client().multiple_random_images(images_number)
 }
 
 /// RANDOM IMAGE FROM A BREED COLLECTION
@@ -119,22 +303,7 @@ pub fn multiple_random_images(images_number: i8) -> Result<Vec<String>, DogAPIEr
 ///
 /// Returns a random dog image from a breed, e.g. hound
 pub fn random_image_by_breed(breed: &str) -> Result<String, DogAPIError> {
-    match get_request(&format!("breed/{}/images/random", breed.trim())) {
-        Ok(response) => {
-            match serde_json::from_str(&response) {
-                Ok(json) => {
-                    let data: Response = json;
-                    if data.status != "success" {
-                        Err(DogAPIError::Error(data.message))
-                    } else {
-                        Ok(data.message)
-                    }
-                },
-                Err(err) => Err(DogAPIError::Error(format!("Something went wrong while reading json: {}", err.to_string())))
-            }
-        },
-        Err(err) => Err(DogAPIError::Error(String::from(err)))
-    }
+    default_client().random_image_by_breed(breed)
 }
 
 /// MULTIPLE IMAGES FROM A BREED COLLECTION
@@ -144,46 +313,7 @@ pub fn random_image_by_breed(breed: &str) -> Result<String, DogAPIError> {
 ///
 /// Return multiple random dog image from a breed, e.g. hound
 pub fn multiple_random_images_by_breed(breed: &str, images_number: i64) -> Result<Vec<String>, DogAPIError> {
-    match get_request(&format!("breed/{}/images/random/{}", breed.trim(), images_number)) {
-        Ok(response) => {
-            match serde_json::from_str(&response) {
-                Ok(json) => {
-                    let data: Value = json;
-                    match data.get("status") {
-                        Some(status) => match data.get("message") {
-                            Some(message) => match status.as_str() {
-                                Some(status_str) => if status_str != "success" {
-                                    let error = match message.as_str() {
-                                        Some(err) => err,
-                                        None => "Something went wrong while reading json"
-                                    };
-                                    Err(DogAPIError::Error(error.to_string()))
-                                } else {
-                                    match message.as_array() {
-                                        Some(array) => {
-                                            let mut vector = vec![];
-                                            for i in array.iter() {
-                                                if let Some(url) = i.as_str() {
-                                                    vector.push(url.to_string());
-                                                }
-                                            }
-                                            Ok(vector)
-                                        },
-                                        None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                                    }
-                                },
-                                None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                            },
-                            None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                        },
-                        None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                    }
-                },
-                Err(err) => Err(DogAPIError::Error(format!("Something went wrong while reading json: {}", err.to_string())))
-            }
-        },
-        Err(err) => Err(DogAPIError::Error(String::from(err)))
-    }
+    default_client().multiple_random_images_by_breed(breed, images_number)
 }
 
 /// ALL IMAGES FROM A BREED COLLECTION
@@ -192,46 +322,16 @@ pub fn multiple_random_images_by_breed(breed: &str, images_number: i64) -> Resul
 ///
 /// Returns an array of all the images from a breed, e.g. hound
 pub fn images_by_breed(breed: &str) -> Result<Vec<String>, DogAPIError> {
-    match get_request(&format!("breed/{}/images", breed.trim())) {
-        Ok(response) => {
-            match serde_json::from_str(&response) {
-                Ok(json) => {
-                    let data: Value = json;
-                    match data.get("status") {
-                        Some(status) => match data.get("message") {
-                            Some(message) => match status.as_str() {
-                                Some(status_str) => if status_str != "success" {
-                                    let error = match message.as_str() {
-                                        Some(err) => err,
-                                        None => "Something went wrong while reading json"
-                                    };
-                                    Err(DogAPIError::Error(error.to_string()))
-                                } else {
-                                    match message.as_array() {
-                                        Some(array) => {
-                                            let mut vector = vec![];
-                                            for i in array.iter() {
-                                                if let Some(url) = i.as_str() {
-                                                    vector.push(url.to_string());
-                                                }
-                                            }
-                                            Ok(vector)
-                                        },
-                                        None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                                    }
-                                },
-                                None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                            },
-                            None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                        },
-                        None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                    }
-                },
-                Err(err) => Err(DogAPIError::Error(format!("Something went wrong while reading json: {}", err.to_string())))
-            }
-        },
-        Err(err) => Err(DogAPIError::Error(String::from(err)))
-    }
+    default_client().images_by_breed(breed)
+}
+
+/// STREAM ALL IMAGES FROM A BREED COLLECTION
+///
+/// * `breed` breed name
+///
+/// Returns an iterator over all the images from a breed, decoded while the response is received
+pub fn images_by_breed_iter(breed: &str) -> Result<ImageIter, DogAPIError> {
+    default_client().images_by_breed_iter(breed)
 }
 
 /// SINGLE RANDOM IMAGE FROM A SUB BREED COLLECTION
@@ -241,22 +341,7 @@ pub fn images_by_breed(breed: &str) -> Result<Vec<String>, DogAPIError> {
 ///
 /// Returns a random dog image from a sub-breed, e.g. Afghan Hound
 pub fn random_image_by_sub_breed(breed: &str, sub_breed: &str) -> Result<String, DogAPIError> {
-    match get_request(&format!("breed/{}/{}/images/random", breed.trim(), sub_breed.trim())) {
-        Ok(response) => {
-            match serde_json::from_str(&response) {
-                Ok(json) => {
-                    let data: Response = json;
-                    if data.status != "success" {
-                        Err(DogAPIError::Error(data.message))
-                    } else {
-                        Ok(data.message)
-                    }
-                },
-                Err(err) => Err(DogAPIError::Error(format!("Something went wrong while reading json: {}", err.to_string())))
-            }
-        },
-        Err(err) => Err(DogAPIError::Error(String::from(err)))
-    }
+    default_client().random_image_by_sub_breed(breed, sub_breed)
 }
 
 /// MULTIPLE IMAGES FROM A SUB-BREED COLLECTION
@@ -267,46 +352,7 @@ pub fn random_image_by_sub_breed(breed: &str, sub_breed: &str) -> Result<String,
 ///
 /// Return multiple random dog images from a sub-breed, e.g. Afghan Hound
 pub fn multiple_random_images_by_sub_breed(breed: &str, sub_breed: &str, images_number: i64) -> Result<Vec<String>, DogAPIError> {
-    match get_request(&format!("breed/{}/{}/images/random/{}", breed.trim(), sub_breed.trim(),images_number)) {
-        Ok(response) => {
-            match serde_json::from_str(&response) {
-                Ok(json) => {
-                    let data: Value = json;
-                    match data.get("status") {
-                        Some(status) => match data.get("message") {
-                            Some(message) => match status.as_str() {
-                                Some(status_str) => if status_str != "success" {
-                                    let error = match message.as_str() {
-                                        Some(err) => err,
-                                        None => "Something went wrong while reading json"
-                                    };
-                                    Err(DogAPIError::Error(error.to_string()))
-                                } else {
-                                    match message.as_array() {
-                                        Some(array) => {
-                                            let mut vector = vec![];
-                                            for i in array.iter() {
-                                                if let Some(url) = i.as_str() {
-                                                    vector.push(url.to_string());
-                                                }
-                                            }
-                                            Ok(vector)
-                                        },
-                                        None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                                    }
-                                },
-                                None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                            },
-                            None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                        },
-                        None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                    }
-                },
-                Err(err) => Err(DogAPIError::Error(format!("Something went wrong while reading json: {}", err.to_string())))
-            }
-        },
-        Err(err) => Err(DogAPIError::Error(String::from(err)))
-    }
+    default_client().multiple_random_images_by_sub_breed(breed, sub_breed, images_number)
 }
 
 /// LIST ALL SUB-BREED IMAGES
@@ -316,102 +362,24 @@ pub fn multiple_random_images_by_sub_breed(breed: &str, sub_breed: &str, images_
 ///
 /// Returns an array of all the images from the sub-breed
 pub fn images_by_sub_breed(breed: &str, sub_breed: &str) -> Result<Vec<String>, DogAPIError> {
-    match get_request(&format!("breed/{}/{}/images", breed.trim(), sub_breed.trim())) {
-        Ok(response) => {
-            match serde_json::from_str(&response) {
-                Ok(json) => {
-                    let data: Value = json;
-                    match data.get("status") {
-                        Some(status) => match data.get("message") {
-                            Some(message) => match status.as_str() {
-                                Some(status_str) => if status_str != "success" {
-                                    let error = match message.as_str() {
-                                        Some(err) => err,
-                                        None => "Something went wrong while reading json"
-                                    };
-                                    Err(DogAPIError::Error(error.to_string()))
-                                } else {
-                                    match message.as_array() {
-                                        Some(array) => {
-                                            let mut vector = vec![];
-                                            for i in array.iter() {
-                                                if let Some(url) = i.as_str() {
-                                                    vector.push(url.to_string());
-                                                }
-                                            }
-                                            Ok(vector)
-                                        },
-                                        None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                                    }
-                                },
-                                None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                            },
-                            None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                        },
-                        None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                    }
-                },
-                Err(err) => Err(DogAPIError::Error(format!("Something went wrong while reading json: {}", err.to_string())))
-            }
-        },
-        Err(err) => Err(DogAPIError::Error(String::from(err)))
-    }
+    default_client().images_by_sub_breed(breed, sub_breed)
+}
+
+/// STREAM ALL SUB-BREED IMAGES
+///
+/// * `breed` breed name
+/// * `sub_breed` sub_breed name
+///
+/// Returns an iterator over all the images from the sub-breed, decoded while the response is received
+pub fn images_by_sub_breed_iter(breed: &str, sub_breed: &str) -> Result<ImageIter, DogAPIError> {
+    default_client().images_by_sub_breed_iter(breed, sub_breed)
 }
 
 /// LIST ALL BREEDS
 ///
 /// Returns map of all the breeds as keys and sub-breeds as values if it has
 pub fn breeds_list() -> Result<HashMap<String, Option<Vec<String>>>, DogAPIError> {
-    match get_request("breeds/list/all") {
-        Ok(response) => {
-            match serde_json::from_str(&response) {
-                Ok(json) => {
-                    let data: Value = json;
-                    match data.get("status") {
-                        Some(status) => match data.get("message") {
-                            Some(message) => match status.as_str() {
-                                Some(status_str) => if status_str != "success" {
-                                    let error = match message.as_str() {
-                                        Some(err) => err,
-                                        None => "Something went wrong while reading json"
-                                    };
-                                    Err(DogAPIError::Error(error.to_string()))
-                                } else {
-                                    match message.as_object() {
-                                        Some(obj) => {
-                                            let mut map= HashMap::new();
-                                            for (i,v) in obj {
-                                                if let Some(value) = v.as_array() {
-                                                    if value.is_empty() {
-                                                        map.insert(i.to_string(),None);
-                                                        continue
-                                                    }
-                                                    let mut vector = vec![];
-                                                    for val in value.iter() {
-                                                        if let Some(value) = val.as_str() {
-                                                            vector.push(value.to_string())
-                                                        }
-                                                    }
-                                                    map.insert(i.to_string(),Some(vector));
-                                                }
-                                            }
-                                            Ok(map)
-                                        },
-                                        None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                                    }
-                                },
-                                None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                            },
-                            None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                        },
-                        None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                    }
-                },
-                Err(err) => Err(DogAPIError::Error(format!("Something went wrong while reading json: {}", err.to_string())))
-            }
-        },
-        Err(err) => Err(DogAPIError::Error(String::from(err)))
-    }
+    default_client().breeds_list()
 }
 
 /// LIST ALL SUB-BREEDS
@@ -420,48 +388,14 @@ pub fn breeds_list() -> Result<HashMap<String, Option<Vec<String>>>, DogAPIError
 ///
 /// Returns an array of all the sub-breeds from a breed if it has sub-breeds
 pub fn sub_breeds_list(breed: &str) -> Result<Option<Vec<String>>, DogAPIError> {
-    match get_request(&format!("breed/{}/list", breed.trim())) {
-        Ok(response) => {
-            match serde_json::from_str(&response) {
-                Ok(json) => {
-                    let data: Value = json;
-                    match data.get("status") {
-                        Some(status) => match data.get("message") {
-                            Some(message) => match status.as_str() {
-                                Some(status_str) => if status_str != "success" {
-                                    let error = match message.as_str() {
-                                        Some(err) => err,
-                                        None => "Something went wrong while reading json"
-                                    };
-                                    Err(DogAPIError::Error(error.to_string()))
-                                } else {
-                                    match message.as_array() {
-                                        Some(array) => {
-                                            if array.is_empty() {
-                                                Ok(None)
-                                            } else {
-                                                let mut vector = vec![];
-                                                for i in array.iter() {
-                                                    if let Some(url) = i.as_str() {
-                                                        vector.push(url.to_string());
-                                                    }
-                                                }
-                                                Ok(Some(vector))
-                                            }
-                                        },
-                                        None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                                    }
-                                },
-                                None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                            },
-                            None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                        },
-                        None => Err(DogAPIError::Error(String::from("Something went wrong while reading json")))
-                    }
-                },
-                Err(err) => Err(DogAPIError::Error(format!("Something went wrong while reading json: {}", err.to_string())))
-            }
-        },
-        Err(err) => Err(DogAPIError::Error(String::from(err)))
-    }
-}
\ No newline at end of file
+    default_client().sub_breeds_list(breed)
+}
+
+/// DOWNLOAD AN IMAGE
+///
+/// * `url` image url, e.g. returned by [`random_image`]
+///
+/// Returns the image bytes
+pub fn download_image(url: &str) -> Result<Vec<u8>, DogAPIError> {
+    default_client().download_image(url)
+}
@@ -16,9 +16,42 @@
 use std::error::Error;
 use std::fmt::{Display, Result, Formatter};
 
-#[derive(Debug)]
+/// Error of the client
+///
+/// *NOTE* ~ New variants may be added in minor releases, `match` it with a wildcard arm
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum DogAPIError {
-    Error(String)
+    Error(String),
+    /// The response body exceeded the configured limit in bytes
+    ResponseTooLarge(u64),
+    /// The API answered with an error
+    Api {
+        /// Error message, e.g. `Breed not found (master breed does not exist)`
+        message: String,
+        /// `code` field of the error response, e.g. `404`
+        code: Option<u16>,
+        /// HTTP status of the response
+        status: Option<u16>
+    }
+}
+
+impl DogAPIError {
+    /// Returns the `code` field of the API error response, if any
+    pub fn code(&self) -> Option<u16> {
+        match *self {
+            DogAPIError::Api { code, .. } => code,
+            _ => None
+        }
+    }
+
+    /// Returns the HTTP status of the response, if one was received
+    pub fn status(&self) -> Option<u16> {
+        match *self {
+            DogAPIError::Api { status, .. } => status,
+            _ => None
+        }
+    }
 }
 
 impl Error for DogAPIError {}
@@ -26,7 +59,9 @@ impl Error for DogAPIError {}
 impl Display for DogAPIError {
     fn fmt(&self, f: &mut Formatter) -> Result {
         match *self {
-            DogAPIError::Error(ref message) => write!(f, "{}", message)
+            DogAPIError::Error(ref message) => write!(f, "{}", message),
+            DogAPIError::ResponseTooLarge(limit) => write!(f, "Response body exceeds the limit of {} bytes", limit),
+            DogAPIError::Api { ref message, .. } => write!(f, "{}", message)
         }
     }
 }
\ No newline at end of file
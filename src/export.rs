@@ -0,0 +1,168 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! Breed hierarchy as Graphviz DOT, Mermaid and Markdown
+//!
+//! A catalog is the map returned by [`breeds_list`](crate::breeds_list), breeds and
+//! sub-breeds are written in alphabetical order.
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+/// Returns the hierarchy as a Graphviz DOT graph
+///
+/// * `catalog` breeds as keys and their sub-breeds as values
+pub fn dot(catalog: &HashMap<String, Option<Vec<String>>>) -> String {
+    let mut dot = String::from("digraph breeds {\n    rankdir=LR;\n    node [shape=box];\n");
+    for (breed, sub_breeds) in sorted(catalog) {
+        let _ = writeln!(dot, "    \"{}\";", dot_escape(breed));
+        for sub_breed in sub_breeds {
+            let id = dot_escape(&format!("{}/{}", breed, sub_breed));
+            let _ = writeln!(dot, "    \"{}\" [label=\"{}\"];", id, dot_escape(sub_breed));
+            let _ = writeln!(dot, "    \"{}\" -> \"{}\";", dot_escape(breed), id);
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Returns the hierarchy as a Mermaid flowchart
+///
+/// * `catalog` breeds as keys and their sub-breeds as values
+pub fn mermaid(catalog: &HashMap<String, Option<Vec<String>>>) -> String {
+    let mut mermaid = String::from("graph LR\n");
+    for (breed, sub_breeds) in sorted(catalog) {
+        let id = mermaid_id(breed);
+        let _ = writeln!(mermaid, "    {}[\"{}\"]", id, mermaid_escape(breed));
+        for sub_breed in sub_breeds {
+            let _ = writeln!(mermaid, "    {} --> {}[\"{}\"]", id, mermaid_id(&format!("{}/{}", breed, sub_breed)), mermaid_escape(sub_breed));
+        }
+    }
+    mermaid
+}
+
+/// Returns the hierarchy as a nested Markdown list
+///
+/// * `catalog` breeds as keys and their sub-breeds as values
+pub fn markdown(catalog: &HashMap<String, Option<Vec<String>>>) -> String {
+    let mut markdown = String::new();
+    for (breed, sub_breeds) in sorted(catalog) {
+        let _ = writeln!(markdown, "- {}", markdown_escape(breed));
+        for sub_breed in sub_breeds {
+            let _ = writeln!(markdown, "  - {}", markdown_escape(sub_breed));
+        }
+    }
+    markdown
+}
+
+fn sorted(catalog: &HashMap<String, Option<Vec<String>>>) -> BTreeMap<&String, Vec<&String>> {
+    catalog.iter()
+        .map(|(breed, sub_breeds)| {
+            let mut sub_breeds: Vec<&String> = sub_breeds.iter().flatten().collect();
+            sub_breeds.sort();
+            (breed, sub_breeds)
+        })
+        .collect()
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Mermaid node ids only allow ascii letters, digits and `_`, every other character, `_`
+/// included, is written `_<hex code>_` so distinct paths keep distinct ids (`a/b` is
+/// `breed_a_2f_b`, `a__b` is `breed_a_5f__5f_b`), the prefix keeps breeds like `end`
+/// from clashing with keywords
+fn mermaid_id(path: &str) -> String {
+    let mut id = String::from("breed_");
+    for c in path.chars() {
+        if c.is_ascii_alphanumeric() {
+            id.push(c);
+        } else {
+            let _ = write!(id, "_{:x}_", c as u32);
+        }
+    }
+    id
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+fn markdown_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::catalog;
+
+    #[test]
+    fn mermaid_ids_do_not_collide() {
+        let paths = ["a/b", "a__b", "a_b", "a-b", "a b", "a_2f_b", "ab", "a/b_", "a_/b"];
+        let ids: std::collections::HashSet<String> = paths.iter().map(|path| mermaid_id(path)).collect();
+        assert_eq!(ids.len(), paths.len());
+        for id in ids {
+            assert!(id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'), "{}", id);
+        }
+    }
+
+    #[test]
+    fn mermaid_keeps_breed_and_sub_breed_apart() {
+        let mermaid = mermaid(&catalog(&[("a", Some(&["b"])), ("a__b", None)]));
+        assert_eq!(mermaid, "graph LR\n    breed_a[\"a\"]\n    breed_a --> breed_a_2f_b[\"b\"]\n    breed_a_5f__5f_b[\"a__b\"]\n");
+    }
+
+    #[test]
+    fn writes_breeds_and_sub_breeds_in_order() {
+        let catalog = catalog(&[("pug", None), ("hound", Some(&["basset", "afghan"])), ("akita", Some(&[]))]);
+        assert_eq!(markdown(&catalog), "- akita\n- hound\n  - afghan\n  - basset\n- pug\n");
+        assert_eq!(dot(&catalog), concat!(
+            "digraph breeds {\n    rankdir=LR;\n    node [shape=box];\n",
+            "    \"akita\";\n",
+            "    \"hound\";\n",
+            "    \"hound/afghan\" [label=\"afghan\"];\n",
+            "    \"hound\" -> \"hound/afghan\";\n",
+            "    \"hound/basset\" [label=\"basset\"];\n",
+            "    \"hound\" -> \"hound/basset\";\n",
+            "    \"pug\";\n",
+            "}\n"
+        ));
+    }
+
+    #[test]
+    fn escapes_labels() {
+        let catalog = catalog(&[("a\"b", Some(&["c\\d"])), ("*end*", None)]);
+        assert!(dot(&catalog).contains("\"a\\\"b/c\\\\d\" [label=\"c\\\\d\"];"));
+        assert!(mermaid(&catalog).contains("breed_a_22_b[\"a#quot;b\"]"));
+        assert!(mermaid(&catalog).contains("breed__2a_end_2a_[\"*end*\"]"));
+        assert_eq!(markdown(&catalog), "- \\*end\\*\n- a\"b\n  - c\\\\d\n");
+    }
+
+    #[test]
+    fn empty_catalog() {
+        let catalog = HashMap::new();
+        assert_eq!(dot(&catalog), "digraph breeds {\n    rankdir=LR;\n    node [shape=box];\n}\n");
+        assert_eq!(mermaid(&catalog), "graph LR\n");
+        assert_eq!(markdown(&catalog), "");
+    }
+}
@@ -0,0 +1,237 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! Static HTML gallery of breed collections
+//!
+//! Writes an `index.html` linking to one page per breed, with lazy-loaded thumbnails linking to
+//! the original images. The pages embed their styles and need nothing but the image host.
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use crate::breed::BreedPath;
+use crate::client::Client;
+use crate::error::DogAPIError;
+use crate::mirror::write_atomic;
+
+const STYLE: &str = "body{margin:0 auto;max-width:1200px;padding:16px;font-family:system-ui,sans-serif;background:#fafafa;color:#222}\
+a{color:inherit}\
+h1{font-size:1.6em}\
+.grid{display:grid;grid-template-columns:repeat(auto-fill,minmax(200px,1fr));gap:12px}\
+.grid a{display:block;text-decoration:none;background:#fff;border-radius:6px;overflow:hidden;box-shadow:0 1px 3px rgba(0,0,0,.15)}\
+.grid img{display:block;width:100%;height:200px;object-fit:cover;background:#ddd}\
+.grid span{display:block;padding:8px}";
+
+/// Page of a breed in a gallery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    /// Breed of the page
+    pub path: BreedPath,
+    /// File name of the page, relative to the gallery directory
+    pub file: String,
+    /// Image urls on the page
+    pub images: Vec<String>,
+    /// Image urls left out of the page because they are not `http` or `https` urls
+    pub rejected: Vec<String>
+}
+
+/// Generator of a static HTML gallery
+#[derive(Debug, Clone)]
+pub struct Gallery {
+    client: Client,
+    dir: PathBuf,
+    title: String,
+    limit: Option<usize>
+}
+
+impl Gallery {
+    /// Creates a generator
+    ///
+    /// * `client` client used to list the images
+    /// * `dir` output directory
+    pub fn new<P: AsRef<Path>>(client: Client, dir: P) -> Gallery {
+        Gallery {
+            client,
+            dir: dir.as_ref().to_path_buf(),
+            title: String::from("Dogs"),
+            limit: None
+        }
+    }
+
+    /// Sets the title of the index page
+    ///
+    /// * `title` title, defaults to `Dogs`
+    pub fn title(mut self, title: &str) -> Gallery {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Sets the maximum number of images per page
+    ///
+    /// * `limit` number of images, all by default
+    pub fn limit(mut self, limit: usize) -> Gallery {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Writes the index and one page per breed
+    ///
+    /// * `paths` breeds of the gallery, e.g. `hound` or `hound/afghan`
+    ///
+    /// Only `http` and `https` image urls are written to the pages, the others are
+    /// listed in [`Page::rejected`].
+    ///
+    /// Returns the written pages
+    pub fn generate(&self, paths: &[BreedPath]) -> Result<Vec<Page>, DogAPIError> {
+        let mut pages: Vec<Page> = vec![];
+        for path in paths {
+            if pages.iter().any(|page| page.path == *path) {
+                continue
+            }
+            let urls = match path.sub_breed {
+                Some(ref sub_breed) => self.client.images_by_sub_breed(&path.breed, sub_breed)?,
+                None => self.client.images_by_breed(&path.breed)?
+            };
+            let mut images = vec![];
+            let mut rejected = vec![];
+            for url in urls {
+                match web_url(&url) {
+                    Some(url) => images.push(url),
+                    None => rejected.push(url)
+                }
+            }
+            if let Some(limit) = self.limit {
+                images.truncate(limit);
+            }
+            pages.push(Page {
+                path: path.clone(),
+                file: file_name(path),
+                images,
+                rejected
+            });
+        }
+        for page in pages.iter() {
+            write_atomic(&self.dir.join(&page.file), self.page(page).as_bytes())?;
+        }
+        write_atomic(&self.dir.join("index.html"), self.index(&pages).as_bytes())?;
+        Ok(pages)
+    }
+
+    fn index(&self, pages: &[Page]) -> String {
+        let mut cards = String::new();
+        for page in pages {
+            let _ = write!(cards, "<a href=\"{}\">", escape(&page.file));
+            if let Some(cover) = page.images.first() {
+                let _ = write!(cards, "<img src=\"{}\" alt=\"{}\" loading=\"lazy\" decoding=\"async\">", escape(cover), escape(&page.path.to_string()));
+            }
+            let _ = writeln!(cards, "<span>{} ({} images)</span></a>", escape(&page.path.to_string()), page.images.len());
+        }
+        document(&self.title, &format!("<h1>{}</h1>\n<div class=\"grid\">\n{}</div>", escape(&self.title), cards))
+    }
+
+    fn page(&self, page: &Page) -> String {
+        let name = page.path.to_string();
+        let mut images = String::new();
+        for url in page.images.iter() {
+            let _ = writeln!(images, "<a href=\"{0}\"><img src=\"{0}\" alt=\"{1}\" loading=\"lazy\" decoding=\"async\"></a>", escape(url), escape(&name));
+        }
+        document(&format!("{} - {}", name, self.title), &format!(
+            "<p><a href=\"index.html\">{}</a></p>\n<h1>{} ({} images)</h1>\n<div class=\"grid\">\n{}</div>",
+            escape(&self.title), escape(&name), page.images.len(), images
+        ))
+    }
+}
+
+fn document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+        escape(title), STYLE, body
+    )
+}
+
+/// Returns the page file of a breed, `<breed>[-<sub-breed>].html`
+///
+/// Every character other than a lowercase ascii letter or a digit, `-` included, is written
+/// `_<hex code>_` so distinct breeds keep distinct files, also on case-insensitive file
+/// systems (`a-b` is `a_2d_b.html`, `a/b` is `a-b.html`). The first letter of a breed named
+/// `index` is escaped too so its page does not replace `index.html`.
+fn file_name(path: &BreedPath) -> String {
+    let mut name = String::new();
+    escape_file_name(&mut name, &path.breed);
+    if let Some(ref sub_breed) = path.sub_breed {
+        name.push('-');
+        escape_file_name(&mut name, sub_breed);
+    } else if name == "index" {
+        name.replace_range(..1, "_69_");
+    }
+    format!("{}.html", name)
+}
+
+fn escape_file_name(name: &mut String, text: &str) {
+    for c in text.chars() {
+        if c.is_ascii_lowercase() || c.is_ascii_digit() {
+            name.push(c);
+        } else {
+            let _ = write!(name, "_{:x}_", c as u32);
+        }
+    }
+}
+
+/// Returns the normalized url if its scheme is `http` or `https`
+fn web_url(url: &str) -> Option<String> {
+    match reqwest::Url::parse(url.trim()) {
+        Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => Some(parsed.to_string()),
+        _ => None
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_http_and_https_urls_are_kept() {
+        assert_eq!(web_url("https://images.dog.ceo/breeds/pug/a.jpg").as_deref(), Some("https://images.dog.ceo/breeds/pug/a.jpg"));
+        assert_eq!(web_url(" HTTP://example.com/a.jpg").as_deref(), Some("http://example.com/a.jpg"));
+        for url in ["javascript:alert(1)", " JavaScript:alert(1)", "java\tscript:alert(1)", "data:image/png;base64,AAAA", "file:///etc/passwd", "//example.com/a.jpg", "a.jpg"] {
+            assert_eq!(web_url(url), None, "{}", url);
+        }
+    }
+
+    #[test]
+    fn file_names_do_not_collide() {
+        let paths = ["a-b", "a/b", "a.b", "a_b", "a__b", "A", "a", "index", "index/a", "hound/afghan"];
+        let files: Vec<String> = paths.iter().map(|path| file_name(&path.parse().unwrap())).collect();
+        let unique: std::collections::HashSet<&String> = files.iter().collect();
+        assert_eq!(unique.len(), paths.len(), "{:?}", files);
+        assert!(!files.contains(&"index.html".to_string()));
+        assert_eq!(file_name(&"hound/afghan".parse().unwrap()), "hound-afghan.html");
+        assert_eq!(file_name(&"a-b".parse().unwrap()), "a_2d_b.html");
+        assert_eq!(file_name(&"index".parse().unwrap()), "_69_ndex.html");
+    }
+}
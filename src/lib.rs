@@ -15,8 +15,31 @@
  */
 //! Dog API client
 mod error;
+mod client;
+mod breed;
+pub mod middleware;
+pub mod metrics;
+pub mod server;
+pub mod mirror;
+pub mod diff;
+pub mod export;
+pub mod gallery;
+pub mod names;
+pub mod stream;
+pub mod parse;
 mod dogapi;
+mod singleflight;
+#[cfg(test)]
+mod testing;
 pub use error::DogAPIError;
+pub use client::Client;
+pub use client::ClientBuilder;
+pub use breed::BreedPath;
+#[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "rustls-tls-native-roots"))]
+pub use reqwest::Certificate;
+pub use reqwest::NoProxy;
+pub use reqwest::Proxy;
+pub use reqwest::header;
 pub use dogapi::random_image;
 pub use dogapi::multiple_random_images;
 pub use dogapi::random_image_by_breed;
@@ -25,5 +48,8 @@ pub use dogapi::random_image_by_sub_breed;
 pub use dogapi::multiple_random_images_by_sub_breed;
 pub use dogapi::images_by_breed;
 pub use dogapi::images_by_sub_breed;
+pub use dogapi::images_by_breed_iter;
+pub use dogapi::images_by_sub_breed_iter;
 pub use dogapi::breeds_list;
-pub use dogapi::sub_breeds_list;
\ No newline at end of file
+pub use dogapi::sub_breeds_list;
+pub use dogapi::download_image;
\ No newline at end of file
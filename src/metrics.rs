@@ -0,0 +1,246 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Outcome of a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Outcome {
+    /// The server answered with a 2xx status
+    Success,
+    /// The server answered with any other status
+    HttpError,
+    /// No response was received, e.g. connection or TLS failure
+    TransportError
+}
+
+impl Outcome {
+    /// Returns the label value of the outcome
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Outcome::Success => "success",
+            Outcome::HttpError => "http_error",
+            Outcome::TransportError => "transport_error"
+        }
+    }
+}
+
+/// Collector the [`Client`](crate::Client) reports into
+///
+/// `endpoint` is the name of the client method, e.g. `images_by_breed` or `download_image`,
+/// so breed names never end up in labels.
+pub trait Metrics: Send + Sync {
+    /// Records a finished request
+    fn record_request(&self, endpoint: &str, outcome: Outcome, duration: Duration);
+
+    /// Records the bytes of a response body
+    fn record_bytes(&self, endpoint: &str, bytes: u64);
+
+    /// Records a lookup in a cache in front of the API, e.g. the response cache of `dogapi-proxy`
+    fn record_cache(&self, _hit: bool) {}
+
+    /// Records a call answered by an identical request already in flight, see
+    /// [`ClientBuilder::single_flight`](crate::ClientBuilder::single_flight)
+    fn record_coalesced(&self, _endpoint: &str) {}
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Recorder {
+    metrics: Option<Arc<dyn Metrics>>
+}
+
+impl Recorder {
+    pub(crate) fn new(metrics: Arc<dyn Metrics>) -> Recorder {
+        Recorder { metrics: Some(metrics) }
+    }
+
+    pub(crate) fn record_request(&self, endpoint: &str, outcome: Outcome, duration: Duration) {
+        if let Some(ref metrics) = self.metrics {
+            metrics.record_request(endpoint, outcome, duration)
+        }
+    }
+
+    pub(crate) fn record_bytes(&self, endpoint: &str, bytes: u64) {
+        if let Some(ref metrics) = self.metrics {
+            metrics.record_bytes(endpoint, bytes)
+        }
+    }
+
+    pub(crate) fn record_cache(&self, hit: bool) {
+        if let Some(ref metrics) = self.metrics {
+            metrics.record_cache(hit)
+        }
+    }
+
+    pub(crate) fn record_coalesced(&self, endpoint: &str) {
+        if let Some(ref metrics) = self.metrics {
+            metrics.record_coalesced(endpoint)
+        }
+    }
+}
+
+impl Debug for Recorder {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "Recorder({})", if self.metrics.is_some() { "enabled" } else { "disabled" })
+    }
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    requests: BTreeMap<(String, Outcome), u64>,
+    durations: BTreeMap<String, Histogram>,
+    bytes: BTreeMap<String, u64>,
+    cache_hits: u64,
+    cache_misses: u64,
+    coalesced: BTreeMap<String, u64>
+}
+
+/// Built-in collector rendering the Prometheus text exposition format
+///
+/// Exposes `dogapi_requests_total`, `dogapi_request_duration_seconds`,
+/// `dogapi_downloaded_bytes_total`, `dogapi_cache_requests_total`, `dogapi_cache_hit_ratio`
+/// and `dogapi_coalesced_requests_total`.
+#[derive(Debug, Default)]
+pub struct PrometheusMetrics {
+    inner: Mutex<Inner>
+}
+
+impl PrometheusMetrics {
+    /// Creates an empty collector
+    pub fn new() -> PrometheusMetrics {
+        PrometheusMetrics::default()
+    }
+
+    /// Renders the collected metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let inner = match self.inner.lock() {
+            Ok(inner) => inner,
+            Err(poisoned) => poisoned.into_inner()
+        };
+        let mut out = String::new();
+        let _ = writeln!(out, "# HELP dogapi_requests_total Requests sent to the Dog API.");
+        let _ = writeln!(out, "# TYPE dogapi_requests_total counter");
+        for ((endpoint, outcome), count) in inner.requests.iter() {
+            let _ = writeln!(out, "dogapi_requests_total{{endpoint=\"{}\",outcome=\"{}\"}} {}", escape(endpoint), outcome.as_str(), count);
+        }
+        let _ = writeln!(out, "# HELP dogapi_request_duration_seconds Latency of requests sent to the Dog API.");
+        let _ = writeln!(out, "# TYPE dogapi_request_duration_seconds histogram");
+        for (endpoint, histogram) in inner.durations.iter() {
+            let endpoint = escape(endpoint);
+            for (i, bound) in BUCKETS.iter().enumerate() {
+                let _ = writeln!(out, "dogapi_request_duration_seconds_bucket{{endpoint=\"{}\",le=\"{}\"}} {}", endpoint, bound, histogram.buckets[i]);
+            }
+            let _ = writeln!(out, "dogapi_request_duration_seconds_bucket{{endpoint=\"{}\",le=\"+Inf\"}} {}", endpoint, histogram.count);
+            let _ = writeln!(out, "dogapi_request_duration_seconds_sum{{endpoint=\"{}\"}} {}", endpoint, histogram.sum);
+            let _ = writeln!(out, "dogapi_request_duration_seconds_count{{endpoint=\"{}\"}} {}", endpoint, histogram.count);
+        }
+        let _ = writeln!(out, "# HELP dogapi_downloaded_bytes_total Bytes received from the Dog API.");
+        let _ = writeln!(out, "# TYPE dogapi_downloaded_bytes_total counter");
+        for (endpoint, bytes) in inner.bytes.iter() {
+            let _ = writeln!(out, "dogapi_downloaded_bytes_total{{endpoint=\"{}\"}} {}", escape(endpoint), bytes);
+        }
+        let _ = writeln!(out, "# HELP dogapi_cache_requests_total Cache lookups in front of the Dog API.");
+        let _ = writeln!(out, "# TYPE dogapi_cache_requests_total counter");
+        let _ = writeln!(out, "dogapi_cache_requests_total{{result=\"hit\"}} {}", inner.cache_hits);
+        let _ = writeln!(out, "dogapi_cache_requests_total{{result=\"miss\"}} {}", inner.cache_misses);
+        let lookups = inner.cache_hits + inner.cache_misses;
+        let ratio = if lookups == 0 { 0.0 } else { inner.cache_hits as f64 / lookups as f64 };
+        let _ = writeln!(out, "# HELP dogapi_cache_hit_ratio Ratio of cache lookups answered from the cache.");
+        let _ = writeln!(out, "# TYPE dogapi_cache_hit_ratio gauge");
+        let _ = writeln!(out, "dogapi_cache_hit_ratio {}", ratio);
+        let _ = writeln!(out, "# HELP dogapi_coalesced_requests_total Calls answered by an identical request already in flight.");
+        let _ = writeln!(out, "# TYPE dogapi_coalesced_requests_total counter");
+        for (endpoint, count) in inner.coalesced.iter() {
+            let _ = writeln!(out, "dogapi_coalesced_requests_total{{endpoint=\"{}\"}} {}", escape(endpoint), count);
+        }
+        out
+    }
+
+    fn with<F: FnOnce(&mut Inner)>(&self, f: F) {
+        match self.inner.lock() {
+            Ok(mut inner) => f(&mut inner),
+            Err(poisoned) => f(&mut poisoned.into_inner())
+        }
+    }
+}
+
+impl Metrics for PrometheusMetrics {
+    fn record_request(&self, endpoint: &str, outcome: Outcome, duration: Duration) {
+        self.with(|inner| {
+            *inner.requests.entry((endpoint.to_string(), outcome)).or_insert(0) += 1;
+            let seconds = duration.as_secs_f64();
+            let histogram = inner.durations.entry(endpoint.to_string()).or_default();
+            for (i, bound) in BUCKETS.iter().enumerate() {
+                if seconds <= *bound {
+                    histogram.buckets[i] += 1;
+                }
+            }
+            histogram.sum += seconds;
+            histogram.count += 1;
+        })
+    }
+
+    fn record_bytes(&self, endpoint: &str, bytes: u64) {
+        self.with(|inner| *inner.bytes.entry(endpoint.to_string()).or_insert(0) += bytes)
+    }
+
+    fn record_cache(&self, hit: bool) {
+        self.with(|inner| if hit {
+            inner.cache_hits += 1
+        } else {
+            inner.cache_misses += 1
+        })
+    }
+
+    fn record_coalesced(&self, endpoint: &str) {
+        self.with(|inner| *inner.coalesced.entry(endpoint.to_string()).or_insert(0) += 1)
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coalesced_requests_do_not_count_as_cache_lookups() {
+        let metrics = PrometheusMetrics::new();
+        metrics.record_cache(true);
+        metrics.record_cache(false);
+        metrics.record_cache(false);
+        metrics.record_coalesced("breeds_list");
+        metrics.record_coalesced("breeds_list");
+        let rendered = metrics.render();
+        assert!(rendered.contains("dogapi_cache_requests_total{result=\"hit\"} 1\n"));
+        assert!(rendered.contains("dogapi_cache_requests_total{result=\"miss\"} 2\n"));
+        assert!(rendered.contains(&format!("dogapi_cache_hit_ratio {}\n", 1.0 / 3.0)));
+        assert!(rendered.contains("dogapi_coalesced_requests_total{endpoint=\"breeds_list\"} 2\n"));
+    }
+}
@@ -0,0 +1,91 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use crate::error::DogAPIError;
+use reqwest::header::HeaderMap;
+
+/// Outgoing request seen by a [`Middleware`]
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// Request url, can be rewritten e.g. to point at a mirror
+    pub url: String,
+    /// Request headers, sent on top of the client's default headers
+    pub headers: HeaderMap
+}
+
+/// Incoming response seen by a [`Middleware`]
+#[derive(Debug, Clone)]
+pub struct Response {
+    /// Url the request was sent to
+    pub url: String,
+    /// HTTP status code
+    pub status: u16,
+    /// Response headers
+    pub headers: HeaderMap,
+    /// Response body
+    pub body: Vec<u8>
+}
+
+/// Interceptor for every request made by a [`Client`](crate::Client)
+///
+/// Request hooks run in the order the middleware were added, response hooks in the reverse order.
+/// Returning an error from a hook aborts the call with that error.
+pub trait Middleware: Send + Sync {
+    /// Inspects or modifies an outgoing request
+    fn on_request(&self, _request: &mut Request) -> Result<(), DogAPIError> {
+        Ok(())
+    }
+
+    /// Inspects or modifies an incoming response
+    ///
+    /// Streamed image lists (`images_by_breed_iter`, `images_by_sub_breed_iter`) are decoded
+    /// while they are received, their response is seen with an empty body and changes to the body are ignored
+    fn on_response(&self, _response: &mut Response) -> Result<(), DogAPIError> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Chain {
+    middleware: Vec<Arc<dyn Middleware>>
+}
+
+impl Chain {
+    pub(crate) fn push(&mut self, middleware: Arc<dyn Middleware>) {
+        self.middleware.push(middleware)
+    }
+
+    pub(crate) fn on_request(&self, request: &mut Request) -> Result<(), DogAPIError> {
+        for middleware in self.middleware.iter() {
+            middleware.on_request(request)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn on_response(&self, response: &mut Response) -> Result<(), DogAPIError> {
+        for middleware in self.middleware.iter().rev() {
+            middleware.on_response(response)?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for Chain {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "Chain({} middleware)", self.middleware.len())
+    }
+}
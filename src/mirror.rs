@@ -0,0 +1,520 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! Offline copy of the Dog API dataset
+//!
+//! Images are stored in the layout of the upstream image host, `breeds/<breed>-<sub-breed>/<file>`,
+//! so the directory can be served with `dogapi-server`.
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::client::Client;
+use crate::error::DogAPIError;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+/// Format version of the manifest written by this crate
+pub const MANIFEST_FORMAT: u32 = 1;
+
+/// Name of the manifest file in the mirror directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Image stored in a mirror
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Image {
+    /// Upstream url
+    pub url: String,
+    /// Path relative to the mirror directory, e.g. `breeds/hound-afghan/n02088094_1003.jpg`
+    pub path: String,
+    /// Size in bytes
+    pub size: u64
+}
+
+/// Snapshot of a mirror
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Format version of the manifest, see [`MANIFEST_FORMAT`]
+    pub format: u32,
+    /// Snapshot number, incremented by every sync
+    pub version: u64,
+    /// Creation time in seconds since the unix epoch
+    pub created: u64,
+    /// Breeds as keys and their sub-breeds as values
+    pub breeds: BTreeMap<String, Vec<String>>,
+    /// Images of every breed path, e.g. `hound/afghan` or `affenpinscher`
+    pub images: BTreeMap<String, Vec<Image>>
+}
+
+/// Image that could not be downloaded
+#[derive(Debug)]
+pub struct Failure {
+    /// Upstream url
+    pub url: String,
+    /// Cause of the failure
+    pub error: DogAPIError
+}
+
+/// Result of a sync
+#[derive(Debug)]
+pub struct SyncReport {
+    /// Manifest written to the mirror directory
+    pub manifest: Manifest,
+    /// Number of images downloaded
+    pub downloaded: usize,
+    /// Number of images already on disk
+    pub skipped: usize,
+    /// Images that could not be downloaded or whose url was rejected, they are left out of the manifest
+    pub failures: Vec<Failure>
+}
+
+/// Result of an incremental refresh
+#[derive(Debug)]
+pub struct RefreshReport {
+    /// Manifest written to the mirror directory
+    pub manifest: Manifest,
+    /// Breed paths listed upstream but not in the previous snapshot, e.g. `hound/afghan`
+    pub added_breeds: Vec<String>,
+    /// Breed paths of the previous snapshot that are no longer listed upstream
+    pub removed_breeds: Vec<String>,
+    /// Images downloaded by the refresh
+    pub added_images: Vec<Image>,
+    /// Images of the previous snapshot that are no longer listed upstream,
+    /// images are matched by `path` so a change of host or scheme keeps them
+    pub removed_images: Vec<Image>,
+    /// Whether the removed images were deleted from disk
+    pub pruned: bool,
+    /// Images that could not be downloaded or whose url was rejected, they are left out of the manifest
+    pub failures: Vec<Failure>
+}
+
+struct Listing {
+    breeds: BTreeMap<String, Vec<String>>,
+    images: BTreeMap<String, Vec<String>>
+}
+
+struct Pending {
+    leaf: String,
+    url: String,
+    path: String
+}
+
+#[derive(Default)]
+struct Fetched {
+    images: BTreeMap<String, Vec<Image>>,
+    downloaded: usize,
+    skipped: usize,
+    failures: Vec<Failure>
+}
+
+/// Mirror of the Dog API dataset in a local directory
+#[derive(Debug, Clone)]
+pub struct Mirror {
+    client: Client,
+    dir: PathBuf,
+    concurrency: usize
+}
+
+impl Manifest {
+    /// Reads a manifest file
+    ///
+    /// * `path` path of the manifest
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Manifest, DogAPIError> {
+        let content = match fs::read_to_string(path.as_ref()) {
+            Ok(content) => content,
+            Err(err) => return Err(DogAPIError::Error(format!("Something went wrong while reading {}: {}", path.as_ref().display(), err)))
+        };
+        match serde_json::from_str(&content) {
+            Ok(manifest) => Ok(manifest),
+            Err(err) => Err(DogAPIError::Error(format!("Something went wrong while reading json: {}", err)))
+        }
+    }
+
+    /// Writes the manifest file, replacing an existing one atomically
+    ///
+    /// * `path` path of the manifest
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), DogAPIError> {
+        let json = match serde_json::to_string_pretty(self) {
+            Ok(json) => json,
+            Err(err) => return Err(DogAPIError::Error(format!("Something went wrong while writing json: {}", err)))
+        };
+        write_atomic(path.as_ref(), json.as_bytes())
+    }
+
+    /// Returns the breeds in the shape of [`breeds_list`](crate::breeds_list)
+    pub fn catalog(&self) -> HashMap<String, Option<Vec<String>>> {
+        self.breeds.iter()
+            .map(|(breed, sub_breeds)| (breed.clone(), if sub_breeds.is_empty() { None } else { Some(sub_breeds.clone()) }))
+            .collect()
+    }
+}
+
+impl Mirror {
+    /// Creates a mirror
+    ///
+    /// * `client` client used to list and download the images
+    /// * `dir` mirror directory
+    pub fn new<P: AsRef<Path>>(client: Client, dir: P) -> Mirror {
+        Mirror {
+            client,
+            dir: dir.as_ref().to_path_buf(),
+            concurrency: 4
+        }
+    }
+
+    /// Sets the number of parallel downloads
+    ///
+    /// * `concurrency` number of downloads, defaults to 4
+    pub fn concurrency(mut self, concurrency: usize) -> Mirror {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Returns the mirror directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the manifest of the last sync if there is one
+    pub fn manifest(&self) -> Result<Option<Manifest>, DogAPIError> {
+        let path = self.dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None)
+        }
+        Manifest::read(path).map(Some)
+    }
+
+    /// FULL SYNC
+    ///
+    /// Walks the breeds list, downloads every image that is not on disk yet
+    /// and writes a new snapshot manifest
+    pub fn sync(&self) -> Result<SyncReport, DogAPIError> {
+        let version = match self.manifest()? {
+            Some(manifest) => manifest.version + 1,
+            None => 1
+        };
+        let listing = self.list()?;
+        let mut pending = vec![];
+        let mut failures = vec![];
+        for (leaf, urls) in listing.images.iter() {
+            for url in urls {
+                match image_path(url) {
+                    Ok(path) => pending.push(Pending { leaf: leaf.clone(), url: url.clone(), path }),
+                    Err(error) => failures.push(Failure { url: url.clone(), error })
+                }
+            }
+        }
+        let mut fetched = self.fetch(pending);
+        failures.append(&mut fetched.failures);
+        let manifest = Manifest {
+            format: MANIFEST_FORMAT,
+            version,
+            created: now(),
+            breeds: listing.breeds,
+            images: fetched.images
+        };
+        manifest.write(self.dir.join(MANIFEST_FILE))?;
+        Ok(SyncReport {
+            manifest,
+            downloaded: fetched.downloaded,
+            skipped: fetched.skipped,
+            failures
+        })
+    }
+
+    /// INCREMENTAL REFRESH
+    ///
+    /// * `prune` delete the images that are no longer listed upstream
+    ///
+    /// Compares the breeds list and image lists with the last snapshot,
+    /// downloads only the new images and writes a new snapshot manifest
+    pub fn refresh(&self, prune: bool) -> Result<RefreshReport, DogAPIError> {
+        let previous = self.manifest()?.unwrap_or_default();
+        let listing = self.list()?;
+        let known: HashMap<&str, &Image> = previous.images.values().flatten().map(|image| (image.path.as_str(), image)).collect();
+        let mut images: BTreeMap<String, Vec<Image>> = BTreeMap::new();
+        let mut listed = HashSet::new();
+        let mut pending = vec![];
+        let mut failures = vec![];
+        for (leaf, urls) in listing.images.iter() {
+            for url in urls {
+                let path = match image_path(url) {
+                    Ok(path) => path,
+                    Err(error) => {
+                        failures.push(Failure { url: url.clone(), error });
+                        continue
+                    }
+                };
+                match known.get(path.as_str()) {
+                    Some(image) => images.entry(leaf.clone()).or_default().push(Image { url: url.clone(), ..(*image).clone() }),
+                    None => pending.push(Pending { leaf: leaf.clone(), url: url.clone(), path: path.clone() })
+                }
+                listed.insert(path);
+            }
+        }
+        let removed_images: Vec<Image> = previous.images.values().flatten()
+            .filter(|image| !listed.contains(&image.path))
+            .cloned()
+            .collect();
+        let (before, after) = (leaves(&previous.breeds), leaves(&listing.breeds));
+        let added_breeds = after.difference(&before).cloned().collect();
+        let removed_breeds = before.difference(&after).cloned().collect();
+        let fetched = self.fetch(pending);
+        failures.extend(fetched.failures);
+        let mut added_images = vec![];
+        for (leaf, list) in fetched.images {
+            added_images.extend(list.iter().cloned());
+            images.entry(leaf).or_default().extend(list);
+        }
+        for list in images.values_mut() {
+            list.sort_by(|a, b| a.path.cmp(&b.path));
+        }
+        added_images.sort_by(|a, b| a.path.cmp(&b.path));
+        if prune {
+            let kept: HashSet<&str> = images.values().flatten().map(|image| image.path.as_str()).collect();
+            for image in removed_images.iter().filter(|image| !kept.contains(image.path.as_str())) {
+                let file = self.dir.join(&image.path);
+                if let Err(err) = fs::remove_file(&file) {
+                    if err.kind() != std::io::ErrorKind::NotFound {
+                        return Err(DogAPIError::Error(format!("Something went wrong while removing {}: {}", file.display(), err)))
+                    }
+                }
+                if let Some(parent) = file.parent() {
+                    let _ = fs::remove_dir(parent);
+                }
+            }
+        }
+        let manifest = Manifest {
+            format: MANIFEST_FORMAT,
+            version: previous.version + 1,
+            created: now(),
+            breeds: listing.breeds,
+            images
+        };
+        manifest.write(self.dir.join(MANIFEST_FILE))?;
+        Ok(RefreshReport {
+            manifest,
+            added_breeds,
+            removed_breeds,
+            added_images,
+            removed_images,
+            pruned: prune,
+            failures
+        })
+    }
+
+    fn list(&self) -> Result<Listing, DogAPIError> {
+        let mut breeds = BTreeMap::new();
+        let mut images = BTreeMap::new();
+        for (breed, sub_breeds) in self.client.breeds_list()? {
+            match sub_breeds {
+                Some(ref sub_breeds) => for sub_breed in sub_breeds {
+                    images.insert(format!("{}/{}", breed, sub_breed), self.client.images_by_sub_breed(&breed, sub_breed)?);
+                },
+                None => {
+                    images.insert(breed.clone(), self.client.images_by_breed(&breed)?);
+                }
+            }
+            let mut sub_breeds = sub_breeds.unwrap_or_default();
+            sub_breeds.sort();
+            breeds.insert(breed, sub_breeds);
+        }
+        Ok(Listing { breeds, images })
+    }
+
+    fn fetch(&self, pending: Vec<Pending>) -> Fetched {
+        let queue = Mutex::new(pending.into_iter());
+        let results = Mutex::new(Fetched::default());
+        thread::scope(|scope| {
+            for _ in 0..self.concurrency {
+                scope.spawn(|| loop {
+                    let next = match queue.lock() {
+                        Ok(mut queue) => queue.next(),
+                        Err(_) => None
+                    };
+                    let Pending { leaf, url, path } = match next {
+                        Some(next) => next,
+                        None => break
+                    };
+                    let file = self.dir.join(&path);
+                    let result = match fs::metadata(&file) {
+                        Ok(metadata) if metadata.is_file() && metadata.len() > 0 => Ok((metadata.len(), false)),
+                        _ => self.client.download_image(&url)
+                            .and_then(|bytes| write_atomic(&file, &bytes).map(|_| (bytes.len() as u64, true)))
+                    };
+                    let mut results = match results.lock() {
+                        Ok(results) => results,
+                        Err(poisoned) => poisoned.into_inner()
+                    };
+                    match result {
+                        Ok((size, downloaded)) => {
+                            if downloaded {
+                                results.downloaded += 1
+                            } else {
+                                results.skipped += 1
+                            }
+                            results.images.entry(leaf).or_default().push(Image { url, path, size });
+                        },
+                        Err(error) => results.failures.push(Failure { url, error })
+                    }
+                });
+            }
+        });
+        let mut fetched = match results.into_inner() {
+            Ok(results) => results,
+            Err(poisoned) => poisoned.into_inner()
+        };
+        for list in fetched.images.values_mut() {
+            list.sort_by(|a, b| a.path.cmp(&b.path));
+        }
+        fetched
+    }
+}
+
+impl Display for RefreshReport {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        writeln!(f, "Snapshot {}", self.manifest.version)?;
+        for breed in self.added_breeds.iter() {
+            writeln!(f, "+ breed {}", breed)?;
+        }
+        for breed in self.removed_breeds.iter() {
+            writeln!(f, "- breed {}", breed)?;
+        }
+        for image in self.added_images.iter() {
+            writeln!(f, "+ {}", image.path)?;
+        }
+        for image in self.removed_images.iter() {
+            writeln!(f, "- {}{}", image.path, if self.pruned { "" } else { " (kept on disk)" })?;
+        }
+        for failure in self.failures.iter() {
+            writeln!(f, "! {}: {}", failure.url, failure.error)?;
+        }
+        write!(f, "{} breeds added, {} removed, {} images added, {} removed, {} failed",
+               self.added_breeds.len(), self.removed_breeds.len(), self.added_images.len(), self.removed_images.len(), self.failures.len())
+    }
+}
+
+/// Returns the path of an image relative to a mirror directory
+///
+/// * `url` image url, e.g. `https://images.dog.ceo/breeds/hound-afghan/n02088094_1003.jpg`
+///
+/// The path is the one of the url, e.g. `breeds/hound-afghan/n02088094_1003.jpg`, urls with
+/// fewer than two segments or segments that could leave the directory are rejected.
+pub fn image_path(url: &str) -> Result<String, DogAPIError> {
+    let parsed = match reqwest::Url::parse(url.trim()) {
+        Ok(parsed) => parsed,
+        Err(err) => return Err(DogAPIError::Error(format!("Something went wrong while reading the image url {}: {}", url, err)))
+    };
+    let segments: Vec<&str> = match parsed.path_segments() {
+        Some(segments) => segments.collect(),
+        None => vec![]
+    };
+    if segments.len() < 2 || segments.iter().any(|segment| segment.is_empty() || *segment == "." || *segment == ".." || segment.contains(['\\', ':'])) {
+        return Err(DogAPIError::Error(format!("Something went wrong while reading the image url: {}", url)))
+    }
+    Ok(segments.join("/"))
+}
+
+/// Writes a file through a `.part` file renamed into place, creating the parent directories
+///
+/// * `path` path of the file
+/// * `bytes` content of the file
+///
+/// *NOTE* ~ A reader never sees a partially written file at `path`
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), DogAPIError> {
+    let mut part = path.as_os_str().to_owned();
+    part.push(".part");
+    let part = PathBuf::from(part);
+    let result = match path.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(())
+    }.and_then(|_| fs::write(&part, bytes)).and_then(|_| fs::rename(&part, path));
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(DogAPIError::Error(format!("Something went wrong while writing {}: {}", path.display(), err)))
+    }
+}
+
+/// Breed paths of a breeds list, e.g. `hound/afghan` or `affenpinscher`
+fn leaves(breeds: &BTreeMap<String, Vec<String>>) -> BTreeSet<String> {
+    let mut leaves = BTreeSet::new();
+    for (breed, sub_breeds) in breeds.iter() {
+        if sub_breeds.is_empty() {
+            leaves.insert(breed.clone());
+        } else {
+            leaves.extend(sub_breeds.iter().map(|sub_breed| format!("{}/{}", breed, sub_breed)));
+        }
+    }
+    leaves
+}
+
+fn now() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs(),
+        Err(_) => 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{client, serve, TempDir};
+
+    fn mirror(upstream: &TempDir, dir: &TempDir) -> Mirror {
+        Mirror::new(client(&serve(upstream.path())), dir.path())
+    }
+
+    #[test]
+    fn refresh_after_sync() {
+        let upstream = TempDir::new();
+        upstream.write("breeds/affenpinscher/a1.jpg", b"affenpinscher 1");
+        upstream.write("breeds/hound-afghan/h1.jpg", b"afghan 1");
+        upstream.write("breeds/hound-afghan/h2.jpg", b"afghan 2");
+        fs::create_dir_all(upstream.path().join("breeds/boxer")).unwrap();
+        let dir = TempDir::new();
+
+        let report = mirror(&upstream, &dir).sync().unwrap();
+        assert_eq!((report.downloaded, report.skipped, report.failures.len()), (3, 0, 0));
+        assert_eq!(report.manifest.breeds["boxer"], Vec::<String>::new());
+        assert_eq!(fs::read(dir.path().join("breeds/hound-afghan/h2.jpg")).unwrap(), b"afghan 2");
+
+        let report = mirror(&upstream, &dir).refresh(false).unwrap();
+        assert_eq!(report.manifest.version, 2);
+        assert!(report.added_breeds.is_empty() && report.removed_breeds.is_empty());
+        assert!(report.added_images.is_empty() && report.removed_images.is_empty());
+        assert_eq!(report.manifest.images["hound/afghan"].len(), 2);
+
+        upstream.write("breeds/boxer/b1.jpg", b"boxer 1");
+        upstream.write("breeds/hound-basset/h3.jpg", b"basset 1");
+        fs::remove_file(upstream.path().join("breeds/hound-afghan/h1.jpg")).unwrap();
+        let report = mirror(&upstream, &dir).refresh(true).unwrap();
+        assert_eq!(report.added_breeds, vec!["hound/basset".to_string()]);
+        assert!(report.removed_breeds.is_empty());
+        let paths = |images: &[Image]| images.iter().map(|image| image.path.clone()).collect::<Vec<_>>();
+        assert_eq!(paths(&report.added_images), vec!["breeds/boxer/b1.jpg", "breeds/hound-basset/h3.jpg"]);
+        assert_eq!(paths(&report.removed_images), vec!["breeds/hound-afghan/h1.jpg"]);
+        assert!(!dir.path().join("breeds/hound-afghan/h1.jpg").exists());
+        assert!(dir.path().join("breeds/hound-afghan/h2.jpg").exists());
+        assert_eq!(fs::read(dir.path().join("breeds/boxer/b1.jpg")).unwrap(), b"boxer 1");
+
+        fs::remove_dir_all(upstream.path().join("breeds/boxer")).unwrap();
+        let report = mirror(&upstream, &dir).refresh(false).unwrap();
+        assert_eq!(report.removed_breeds, vec!["boxer".to_string()]);
+        assert!(dir.path().join("breeds/boxer/b1.jpg").exists());
+    }
+}
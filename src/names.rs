@@ -0,0 +1,550 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! Human-readable breed names
+//!
+//! Turns breed paths like `hound/afghan` or `germanshepherd` into names like `Afghan Hound`
+//! or `German Shepherd`, with curated names for the breeds the rules get wrong and
+//! translation tables for other languages, and resolves names like `afghan hound` or `lab`
+//! back to breed paths.
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use crate::breed::BreedPath;
+
+/// Curated English names, keyed by breed path
+const CURATED: &[(&str, &str)] = &[
+    ("african", "African Wild Dog"),
+    ("african/wild", "African Wild Dog"),
+    ("airedale", "Airedale Terrier"),
+    ("appenzeller", "Appenzeller Sennenhund"),
+    ("australian/kelpie", "Australian Kelpie"),
+    ("australian/shepherd", "Australian Shepherd"),
+    ("bluetick", "Bluetick Coonhound"),
+    ("bouvier", "Bouvier des Flandres"),
+    ("brabancon", "Petit Brabançon"),
+    ("bulldog/boston", "Boston Terrier"),
+    ("bullterrier/staffordshire", "Staffordshire Bull Terrier"),
+    ("cattledog/australian", "Australian Cattle Dog"),
+    ("chow", "Chow Chow"),
+    ("clumber", "Clumber Spaniel"),
+    ("corgi/cardigan", "Cardigan Welsh Corgi"),
+    ("cotondetulear", "Coton de Tuléar"),
+    ("danish/swedish", "Danish-Swedish Farmdog"),
+    ("entlebucher", "Entlebucher Mountain Dog"),
+    ("eskimo", "American Eskimo Dog"),
+    ("finnish/lapphund", "Finnish Lapphund"),
+    ("frise/bichon", "Bichon Frise"),
+    ("germanshepherd", "German Shepherd"),
+    ("labrador", "Labrador Retriever"),
+    ("leonberg", "Leonberger"),
+    ("lhasa", "Lhasa Apso"),
+    ("malamute", "Alaskan Malamute"),
+    ("mastiff/bull", "Bullmastiff"),
+    ("mexicanhairless", "Mexican Hairless Dog"),
+    ("mix", "Mixed Breed"),
+    ("mountain/bernese", "Bernese Mountain Dog"),
+    ("mountain/swiss", "Greater Swiss Mountain Dog"),
+    ("ovcharka/caucasian", "Caucasian Shepherd Dog"),
+    ("pekinese", "Pekingese"),
+    ("pembroke", "Pembroke Welsh Corgi"),
+    ("pitbull", "Pit Bull"),
+    ("pointer/germanlonghair", "German Longhaired Pointer"),
+    ("pyrenees", "Great Pyrenees"),
+    ("redbone", "Redbone Coonhound"),
+    ("retriever/chesapeake", "Chesapeake Bay Retriever"),
+    ("retriever/curly", "Curly-Coated Retriever"),
+    ("retriever/flatcoated", "Flat-Coated Retriever"),
+    ("sharpei", "Shar Pei"),
+    ("sheepdog/english", "Old English Sheepdog"),
+    ("shiba", "Shiba Inu"),
+    ("shihtzu", "Shih Tzu"),
+    ("spaniel/brittany", "Brittany"),
+    ("spaniel/irish", "Irish Water Spaniel"),
+    ("spaniel/japanese", "Japanese Chin"),
+    ("spaniel/welsh", "Welsh Springer Spaniel"),
+    ("springer/english", "English Springer Spaniel"),
+    ("stbernard", "St. Bernard"),
+    ("terrier/american", "American Staffordshire Terrier"),
+    ("terrier/dandie", "Dandie Dinmont Terrier"),
+    ("terrier/kerryblue", "Kerry Blue Terrier"),
+    ("terrier/russell", "Jack Russell Terrier"),
+    ("terrier/westhighland", "West Highland White Terrier"),
+    ("terrier/wheaten", "Soft-Coated Wheaten Terrier"),
+    ("waterdog/spanish", "Spanish Water Dog")
+];
+
+/// Breed slugs that are adjectives, written before their sub-breeds, e.g. `australian/kelpie`
+const ADJECTIVES: &[&str] = &["african", "australian", "danish", "finnish"];
+
+/// Common names and nicknames, keyed by breed path
+const ALIASES: &[(&str, &[&str])] = &[
+    ("australian/shepherd", &["aussie", "australian shepherd"]),
+    ("bulldog/english", &["english bulldog", "british bulldog"]),
+    ("bulldog/french", &["frenchie", "french bulldog"]),
+    ("bullterrier/staffordshire", &["staffy", "staffie"]),
+    ("collie/border", &["border collie"]),
+    ("dachshund", &["doxie", "sausage dog", "wiener dog"]),
+    ("dalmatian", &["dalmation"]),
+    ("dane/great", &["great dane"]),
+    ("doberman", &["dobie", "dobermann", "doberman pinscher"]),
+    ("frise/bichon", &["bichon"]),
+    ("germanshepherd", &["gsd", "alsatian", "german shepherd dog"]),
+    ("greyhound/italian", &["iggy", "italian greyhound"]),
+    ("hound/afghan", &["afghan", "afghan hound"]),
+    ("hound/basset", &["basset", "basset hound"]),
+    ("hound/blood", &["bloodhound"]),
+    ("husky", &["siberian husky"]),
+    ("labrador", &["lab", "lab retriever"]),
+    ("mastiff/english", &["english mastiff"]),
+    ("mastiff/tibetan", &["tibetan mastiff"]),
+    ("mix", &["mutt", "mixed"]),
+    ("mountain/bernese", &["bernese", "berner"]),
+    ("newfoundland", &["newfie"]),
+    ("pekinese", &["peke"]),
+    ("pembroke", &["welsh corgi"]),
+    ("pinscher/miniature", &["min pin", "miniature pinscher"]),
+    ("pitbull", &["pit", "pit bull terrier", "american pit bull terrier"]),
+    ("pomeranian", &["pom"]),
+    ("poodle/miniature", &["mini poodle", "miniature poodle"]),
+    ("poodle/standard", &["standard poodle"]),
+    ("poodle/toy", &["toy poodle"]),
+    ("retriever/golden", &["golden", "golden retriever"]),
+    ("rottweiler", &["rottie"]),
+    ("samoyed", &["sammy"]),
+    ("sharpei", &["chinese shar pei"]),
+    ("sheepdog/shetland", &["sheltie"]),
+    ("spaniel/cocker", &["cocker", "cocker spaniel"]),
+    ("stbernard", &["saint bernard"]),
+    ("terrier/russell", &["jack russell", "jrt"]),
+    ("terrier/westhighland", &["westie"]),
+    ("terrier/yorkshire", &["yorkie", "yorkshire terrier"]),
+    ("weimaraner", &["weim"]),
+    ("wolfhound/irish", &["irish wolfhound"])
+];
+
+/// Names of the breeds in another language
+pub trait Translations: Send + Sync {
+    /// Returns the name of a breed, `None` to fall back to the English name
+    fn name(&self, path: &BreedPath) -> Option<String>;
+}
+
+/// Table keyed by breed path, e.g. `hound/afghan` → `Lévrier afghan`
+impl Translations for HashMap<String, String> {
+    fn name(&self, path: &BreedPath) -> Option<String> {
+        self.get(&path.to_string()).cloned()
+    }
+}
+
+/// Display names of breeds
+///
+/// Without a curated name, the sub-breed is put before the breed, unless the breed is an
+/// adjective like `australian`, and every word is capitalized, e.g. `hound/afghan` →
+/// `Afghan Hound`. The curated name of a breed is not reused for its sub-breeds, since it
+/// may not read as a suffix, e.g. `African Wild Dog`. Slugs made of several known words,
+/// e.g. `germanpointer`, are split into words of at least four letters.
+#[derive(Clone)]
+pub struct DisplayNames {
+    overrides: HashMap<String, String>,
+    words: BTreeSet<String>,
+    languages: HashMap<String, Arc<dyn Translations>>
+}
+
+impl DisplayNames {
+    /// Creates display names with the curated English names
+    pub fn new() -> DisplayNames {
+        let mut names = DisplayNames {
+            overrides: HashMap::new(),
+            words: BTreeSet::new(),
+            languages: HashMap::new()
+        };
+        for (path, name) in CURATED {
+            names = names.name_override(path, name);
+        }
+        names
+    }
+
+    /// Sets the English name of a breed
+    ///
+    /// * `path` breed path, e.g. `hound/afghan`
+    /// * `name` display name, e.g. `Afghan Hound`
+    ///
+    /// *NOTE* ~ The words of the name are also used to split other slugs
+    pub fn name_override(mut self, path: &str, name: &str) -> DisplayNames {
+        for word in name.split(|c: char| !c.is_alphabetic()) {
+            if word.chars().count() >= 4 {
+                self.words.insert(word.to_lowercase());
+            }
+        }
+        self.overrides.insert(path.trim().to_lowercase(), name.to_string());
+        self
+    }
+
+    /// Adds a translation table
+    ///
+    /// * `language` language tag, e.g. `fr`
+    /// * `translations` names in that language
+    pub fn language<T: Translations + 'static>(mut self, language: &str, translations: T) -> DisplayNames {
+        self.languages.insert(language.to_string(), Arc::new(translations));
+        self
+    }
+
+    /// Returns the English name of a breed
+    ///
+    /// * `path` breed path
+    pub fn name(&self, path: &BreedPath) -> String {
+        if let Some(name) = self.overrides.get(&path.to_string().to_lowercase()) {
+            return name.clone()
+        }
+        match path.sub_breed {
+            Some(ref sub_breed) if ADJECTIVES.contains(&path.breed.to_lowercase().as_str()) => format!("{} {}", self.words(&path.breed), self.words(sub_breed)),
+            Some(ref sub_breed) => format!("{} {}", self.words(sub_breed), self.words(&path.breed)),
+            None => self.words(&path.breed)
+        }
+    }
+
+    /// Returns the name of a breed in a language
+    ///
+    /// * `path` breed path
+    /// * `language` language tag, e.g. `fr`
+    ///
+    /// Falls back to the English name without a translation
+    pub fn localized(&self, path: &BreedPath, language: &str) -> String {
+        match self.languages.get(language).and_then(|translations| translations.name(path)) {
+            Some(name) => name,
+            None => self.name(path)
+        }
+    }
+
+    /// Capitalizes the words of a slug
+    fn words(&self, slug: &str) -> String {
+        let mut words = vec![];
+        for part in slug.split(|c: char| c == '-' || c == '_' || c.is_whitespace()).filter(|part| !part.is_empty()) {
+            words.extend(self.split(&part.to_lowercase()));
+        }
+        words.iter().map(|word| capitalize(word)).collect::<Vec<String>>().join(" ")
+    }
+
+    /// Splits a slug into known words, keeping it whole unless it is entirely made of them
+    fn split(&self, slug: &str) -> Vec<String> {
+        let chars: Vec<char> = slug.chars().collect();
+        // best[i] is the fewest known words covering the first i characters
+        let mut best: Vec<Option<(usize, usize)>> = vec![None; chars.len() + 1];
+        best[0] = Some((0, 0));
+        for end in 1..=chars.len() {
+            for start in 0..end {
+                let count = match best[start] {
+                    Some((count, _)) => count,
+                    None => continue
+                };
+                let word: String = chars[start..end].iter().collect();
+                if self.words.contains(&word) && best[end].is_none_or(|(best, _)| count + 1 < best) {
+                    best[end] = Some((count + 1, start));
+                }
+            }
+        }
+        if best[chars.len()].is_none() || chars.is_empty() {
+            return vec![slug.to_string()]
+        }
+        let mut words = vec![];
+        let mut end = chars.len();
+        while end > 0 {
+            let start = match best[end] {
+                Some((_, start)) => start,
+                None => break
+            };
+            words.push(chars[start..end].iter().collect());
+            end = start;
+        }
+        words.reverse();
+        words
+    }
+}
+
+impl Default for DisplayNames {
+    fn default() -> DisplayNames {
+        DisplayNames::new()
+    }
+}
+
+impl Debug for DisplayNames {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "DisplayNames({} names, {} languages)", self.overrides.len(), self.languages.len())
+    }
+}
+
+/// Resolver of free-form breed names to breed paths
+///
+/// Names are compared without case, spaces or punctuation, so `German Shepherd`,
+/// `german-shepherd` and `germanshepherd` are the same name.
+#[derive(Debug, Clone)]
+pub struct Aliases {
+    table: HashMap<String, BreedPath>
+}
+
+impl Aliases {
+    /// Creates a resolver with the bundled aliases and the curated names
+    pub fn new() -> Aliases {
+        let mut aliases = Aliases {
+            table: HashMap::new()
+        };
+        for (path, name) in CURATED {
+            aliases = aliases.alias(name, path_of(path));
+        }
+        for (path, names) in ALIASES {
+            for name in names.iter() {
+                aliases = aliases.alias(name, path_of(path));
+            }
+        }
+        aliases
+    }
+
+    /// Adds an alias, replacing the previous one with the same name
+    ///
+    /// *NOTE* ~ An alias equal to a breed path only applies to [`Aliases::resolve`], the
+    /// breed wins in [`Aliases::resolve_in`]
+    ///
+    /// * `name` alias, e.g. `lab`
+    /// * `path` breed path it stands for, e.g. `labrador`
+    pub fn alias(mut self, name: &str, path: BreedPath) -> Aliases {
+        self.table.insert(normalize(name), path);
+        self
+    }
+
+    /// Resolves a name without the breeds list
+    ///
+    /// * `name` alias, e.g. `afghan hound`, or breed path, e.g. `hound/afghan`
+    ///
+    /// Returns `None` for any other name, which [`Aliases::resolve_in`] may still match
+    pub fn resolve(&self, name: &str) -> Option<BreedPath> {
+        if let Some(path) = self.table.get(&normalize(name)) {
+            return Some(path.clone())
+        }
+        let name = name.trim();
+        let canonical = name.split('/').count() <= 2 && name.split('/').all(|slug| !slug.is_empty() && slug.chars().all(|c| c.is_ascii_lowercase()));
+        if canonical {
+            name.parse().ok()
+        } else {
+            None
+        }
+    }
+
+    /// Resolves a name against the breeds list
+    ///
+    /// * `name` alias, breed path or breed name in either word order, e.g. `Afghan Hound`
+    /// * `catalog` breeds list, as returned by `breeds_list`
+    ///
+    /// Breeds and sub-breeds of `catalog` win over aliases, aliases of breeds missing from
+    /// `catalog` are ignored, plural names are accepted. An alias of a sub-breed resolves to
+    /// its breed when `catalog` lists the breed without sub-breeds.
+    pub fn resolve_in(&self, name: &str, catalog: &HashMap<String, Option<Vec<String>>>) -> Option<BreedPath> {
+        let key = normalize(name);
+        match key.strip_suffix('s') {
+            Some(singular) => self.lookup(&key, catalog).or_else(|| self.lookup(singular, catalog)),
+            None => self.lookup(&key, catalog)
+        }
+    }
+
+    fn lookup(&self, key: &str, catalog: &HashMap<String, Option<Vec<String>>>) -> Option<BreedPath> {
+        let mut breeds: Vec<&String> = catalog.keys().collect();
+        breeds.sort();
+        for breed in breeds.iter() {
+            if normalize(breed) == key {
+                return Some(BreedPath::new(breed, None))
+            }
+        }
+        for breed in breeds {
+            let slug = normalize(breed);
+            for sub_breed in catalog[breed].iter().flatten() {
+                let sub_slug = normalize(sub_breed);
+                if format!("{}{}", sub_slug, slug) == key || format!("{}{}", slug, sub_slug) == key {
+                    return Some(BreedPath::new(breed, Some(sub_breed)))
+                }
+            }
+        }
+        let path = self.table.get(key)?;
+        let sub_breeds = catalog.get(&path.breed)?;
+        match path.sub_breed {
+            Some(ref sub_breed) if sub_breeds.iter().flatten().any(|listed| listed == sub_breed) => Some(path.clone()),
+            // e.g. `African Wild Dog` is `african/wild` or `african` depending on the catalog
+            Some(_) if sub_breeds.as_ref().is_none_or(|sub_breeds| sub_breeds.is_empty()) => Some(BreedPath::new(&path.breed, None)),
+            Some(_) => None,
+            None => Some(path.clone())
+        }
+    }
+}
+
+impl Default for Aliases {
+    fn default() -> Aliases {
+        Aliases::new()
+    }
+}
+
+fn path_of(path: &str) -> BreedPath {
+    match path.split_once('/') {
+        Some((breed, sub_breed)) => BreedPath::new(breed, Some(sub_breed)),
+        None => BreedPath::new(path, None)
+    }
+}
+
+/// Lowercases a name and drops everything but letters and digits
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn name(names: &DisplayNames, path: &str) -> String {
+        names.name(&path.parse().unwrap())
+    }
+
+    #[test]
+    fn sub_breeds_come_before_nouns() {
+        let names = DisplayNames::new();
+        assert_eq!(name(&names, "hound/afghan"), "Afghan Hound");
+        assert_eq!(name(&names, "bulldog/french"), "French Bulldog");
+        assert_eq!(name(&names, "bulldog/boston"), "Boston Terrier");
+        assert_eq!(name(&names, "pug"), "Pug");
+    }
+
+    #[test]
+    fn adjective_breeds_come_first() {
+        let names = DisplayNames::new();
+        assert_eq!(name(&names, "australian/shepherd"), "Australian Shepherd");
+        assert_eq!(name(&names, "australian/kelpie"), "Australian Kelpie");
+        assert_eq!(name(&names, "australian/cattle"), "Australian Cattle");
+        assert_eq!(name(&names, "african/wild"), "African Wild Dog");
+    }
+
+    #[test]
+    fn curated_breed_names_are_not_suffixes() {
+        let names = DisplayNames::new();
+        assert_eq!(name(&names, "labrador"), "Labrador Retriever");
+        assert_eq!(name(&names, "labrador/black"), "Black Labrador");
+        assert_eq!(name(&names, "mix/poodle"), "Poodle Mix");
+    }
+
+    #[test]
+    fn splits_known_words() {
+        let names = DisplayNames::new();
+        assert_eq!(name(&names, "terrier/kerryblue"), "Kerry Blue Terrier");
+        assert_eq!(name(&names, "germanblue"), "German Blue");
+        assert_eq!(name(&names, "germanpointer"), "German Pointer");
+        assert_eq!(name(&names, "bulldog"), "Bulldog");
+        assert_eq!(name(&names, "weird-slug_x"), "Weird Slug X");
+    }
+
+    #[test]
+    fn overrides_and_translations() {
+        let french: HashMap<String, String> = HashMap::from([("hound/afghan".to_string(), "Lévrier afghan".to_string())]);
+        let names = DisplayNames::new().name_override("pug", "Mops").language("fr", french);
+        assert_eq!(name(&names, "pug"), "Mops");
+        let afghan = "hound/afghan".parse().unwrap();
+        assert_eq!(names.localized(&afghan, "fr"), "Lévrier afghan");
+        assert_eq!(names.localized(&afghan, "de"), "Afghan Hound");
+        assert_eq!(names.localized(&"pug".parse().unwrap(), "fr"), "Mops");
+    }
+
+    fn catalog() -> HashMap<String, Option<Vec<String>>> {
+        testing::catalog(&[
+            ("corgi", Some(&["cardigan"])),
+            ("pembroke", None),
+            ("hound", Some(&["afghan", "basset"])),
+            ("labrador", None),
+            ("germanshepherd", None),
+            ("australian", Some(&["shepherd"]))
+        ])
+    }
+
+    fn resolve_in(aliases: &Aliases, name: &str) -> Option<String> {
+        aliases.resolve_in(name, &catalog()).map(|path| path.to_string())
+    }
+
+    #[test]
+    fn aliases_do_not_hide_breeds() {
+        let mut slugs: BTreeSet<&str> = BTreeSet::new();
+        for (path, _) in CURATED {
+            slugs.insert(path.split('/').next().unwrap_or(path));
+        }
+        for (path, _) in ALIASES {
+            slugs.insert(path.split('/').next().unwrap_or(path));
+        }
+        for (_, names) in ALIASES {
+            for name in names.iter() {
+                assert!(!slugs.contains(normalize(name).as_str()), "alias '{}' is a breed", name);
+            }
+        }
+    }
+
+    #[test]
+    fn catalog_breeds_win_over_aliases() {
+        let aliases = Aliases::new().alias("hound", BreedPath::new("labrador", None));
+        assert_eq!(resolve_in(&aliases, "corgi").as_deref(), Some("corgi"));
+        assert_eq!(resolve_in(&aliases, "hound").as_deref(), Some("hound"));
+        assert_eq!(aliases.resolve("corgi").map(|path| path.to_string()).as_deref(), Some("corgi"));
+        assert_eq!(resolve_in(&aliases, "welsh corgi").as_deref(), Some("pembroke"));
+    }
+
+    #[test]
+    fn resolves_names_and_aliases() {
+        let aliases = Aliases::new();
+        assert_eq!(resolve_in(&aliases, "Afghan Hound").as_deref(), Some("hound/afghan"));
+        assert_eq!(resolve_in(&aliases, "hound afghan").as_deref(), Some("hound/afghan"));
+        assert_eq!(resolve_in(&aliases, "hound/afghan").as_deref(), Some("hound/afghan"));
+        assert_eq!(resolve_in(&aliases, "Basset Hounds").as_deref(), Some("hound/basset"));
+        assert_eq!(resolve_in(&aliases, "German Shepherd").as_deref(), Some("germanshepherd"));
+        assert_eq!(resolve_in(&aliases, "Australian Shepherd").as_deref(), Some("australian/shepherd"));
+        assert_eq!(resolve_in(&aliases, "lab").as_deref(), Some("labrador"));
+        assert_eq!(resolve_in(&aliases, "Labs").as_deref(), Some("labrador"));
+        assert_eq!(resolve_in(&aliases, "westie"), None);
+        assert_eq!(resolve_in(&aliases, "big dog"), None);
+        assert_eq!(resolve_in(&aliases, "boston terrier"), None);
+        assert_eq!(aliases.resolve("Boston Terrier").map(|path| path.to_string()).as_deref(), Some("bulldog/boston"));
+    }
+
+    #[test]
+    fn resolves_offline() {
+        let aliases = Aliases::new().alias("office dog", BreedPath::new("pug", None));
+        let resolve = |name: &str| aliases.resolve(name).map(|path| path.to_string());
+        assert_eq!(resolve("Office Dog").as_deref(), Some("pug"));
+        assert_eq!(resolve("lab").as_deref(), Some("labrador"));
+        assert_eq!(resolve("hound/afghan").as_deref(), Some("hound/afghan"));
+        assert_eq!(resolve("pug").as_deref(), Some("pug"));
+        assert_eq!(resolve("Big Dog"), None);
+        assert_eq!(resolve("a/b/c"), None);
+    }
+
+    #[test]
+    fn sub_breed_aliases_fall_back_to_breeds_without_sub_breeds() {
+        let aliases = Aliases::new();
+        let resolve = |catalog| aliases.resolve_in("African Wild Dog", &catalog).map(|path| path.to_string());
+        assert_eq!(resolve(testing::catalog(&[("african", None)])).as_deref(), Some("african"));
+        assert_eq!(resolve(testing::catalog(&[("african", Some(&[]))])).as_deref(), Some("african"));
+        assert_eq!(resolve(testing::catalog(&[("african", Some(&["wild"]))])).as_deref(), Some("african/wild"));
+        assert_eq!(resolve(testing::catalog(&[("african", Some(&["painted"]))])), None);
+        assert_eq!(resolve(testing::catalog(&[("hound", None)])), None);
+    }
+}
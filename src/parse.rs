@@ -0,0 +1,89 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! Handling of malformed entries in API responses
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::Arc;
+use crate::error::DogAPIError;
+
+/// How malformed entries of a response are handled
+///
+/// A malformed entry is e.g. a number in an image list or a sub-breed list that is not an array.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParseMode {
+    /// Fails with an error naming the offending entry
+    Strict,
+    /// Skips malformed entries and reports their number as a [`ParseWarning`]
+    #[default]
+    Lenient
+}
+
+/// Malformed entries skipped from a response in [`ParseMode::Lenient`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    /// Name of the client method, e.g. `images_by_breed`
+    pub endpoint: String,
+    /// Number of skipped entries
+    pub skipped: usize
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}: skipped {} malformed entries", self.endpoint, self.skipped)
+    }
+}
+
+type OnWarning = Arc<dyn Fn(&ParseWarning) + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub(crate) struct Parsing {
+    pub(crate) mode: ParseMode,
+    on_warning: Option<OnWarning>
+}
+
+impl Parsing {
+    pub(crate) fn set_on_warning(&mut self, on_warning: OnWarning) {
+        self.on_warning = Some(on_warning)
+    }
+
+    /// Returns the error of a malformed entry in strict mode, `None` if it is skipped
+    pub(crate) fn malformed(&self, entry: &str, reason: &str) -> Option<DogAPIError> {
+        match self.mode {
+            ParseMode::Strict => Some(DogAPIError::Error(format!("Something went wrong while reading json: {} {}", entry, reason))),
+            ParseMode::Lenient => None
+        }
+    }
+
+    /// Reports the entries skipped from a response
+    pub(crate) fn skipped(&self, endpoint: &str, skipped: usize) {
+        if skipped == 0 {
+            return
+        }
+        #[cfg(feature = "tracing")]
+        tracing::warn!(endpoint, skipped, "skipped malformed entries");
+        if let Some(ref on_warning) = self.on_warning {
+            on_warning(&ParseWarning {
+                endpoint: endpoint.to_string(),
+                skipped
+            })
+        }
+    }
+}
+
+impl Debug for Parsing {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "Parsing({:?})", self.mode)
+    }
+}
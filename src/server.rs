@@ -0,0 +1,743 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! Dog API compatible server
+//!
+//! Serves the JSON contract parsed by the [`Client`](crate::Client) from a [`Backend`],
+//! either a local directory laid out like the upstream image host, `breeds/<breed>/<file>`
+//! and `breeds/<breed>-<sub-breed>/<file>`, or a caching proxy in front of another Dog API.
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Debug, Formatter};
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::client::Client;
+use crate::error::DogAPIError;
+use crate::metrics::{Metrics, PrometheusMetrics, Recorder};
+use crate::singleflight::Group;
+use serde_json::{json, Value};
+
+const MAX_IMAGES: usize = 50;
+/// Maximum length of the request line and of each header line
+const MAX_LINE: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+const MAX_CONNECTIONS: usize = 512;
+/// Read and write timeout of the connections, idle keep-alive connections are closed after it
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Error answered by a [`Backend`], sent as the `status`/`message`/`code` envelope
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendError {
+    /// HTTP status and `code` of the envelope
+    pub code: u16,
+    /// Message of the envelope
+    pub message: String
+}
+
+impl BackendError {
+    /// Creates an error
+    pub fn new(code: u16, message: &str) -> BackendError {
+        BackendError { code, message: message.to_string() }
+    }
+
+    fn breed_not_found() -> BackendError {
+        BackendError::new(404, "Breed not found (master breed does not exist)")
+    }
+
+    fn sub_breed_not_found() -> BackendError {
+        BackendError::new(404, "Breed not found (sub breed does not exist)")
+    }
+}
+
+impl From<DogAPIError> for BackendError {
+    fn from(err: DogAPIError) -> BackendError {
+        let code = match err.code().or(err.status()) {
+            Some(404) => 404,
+            _ => 502
+        };
+        BackendError { code, message: err.to_string() }
+    }
+}
+
+/// Source of the data served by a [`Server`]
+///
+/// `base` is the public url of the server, used to build links to images it serves itself.
+pub trait Backend: Send + Sync {
+    /// Returns the breeds as keys and their sub-breeds as values
+    fn breeds(&self) -> Result<BTreeMap<String, Vec<String>>, BackendError>;
+
+    /// Returns every image url of a breed, including its sub-breeds when `sub_breed` is `None`
+    fn images(&self, base: &str, breed: &str, sub_breed: Option<&str>) -> Result<Vec<String>, BackendError>;
+
+    /// Returns up to `count` random image urls, from every breed when `breed` is `None`
+    fn random(&self, base: &str, breed: Option<&str>, sub_breed: Option<&str>, count: usize) -> Result<Vec<String>, BackendError>;
+
+    /// Returns the bytes and content type of an image served from `/breeds/<dir>/<file>`
+    fn file(&self, _dir: &str, _file: &str) -> Option<(Vec<u8>, &'static str)> {
+        None
+    }
+}
+
+#[derive(Debug, Default)]
+struct Breed {
+    images: Vec<String>,
+    sub_breeds: BTreeMap<String, Vec<String>>
+}
+
+/// Backend serving a local directory
+#[derive(Debug)]
+pub struct DirectoryBackend {
+    root: PathBuf,
+    breeds: BTreeMap<String, Breed>,
+    all: Vec<String>,
+    files: HashSet<String>
+}
+
+impl DirectoryBackend {
+    /// Scans a directory
+    ///
+    /// * `root` directory containing the `breeds` directory
+    ///
+    /// The directory is scanned once, create a new backend to pick up new images
+    pub fn new<P: AsRef<Path>>(root: P) -> Result<DirectoryBackend, DogAPIError> {
+        let root = root.as_ref().to_path_buf();
+        let entries = match fs::read_dir(root.join("breeds")) {
+            Ok(entries) => entries,
+            Err(err) => return Err(DogAPIError::Error(format!("Something went wrong while reading {}: {}", root.join("breeds").display(), err)))
+        };
+        let mut backend = DirectoryBackend {
+            root,
+            breeds: BTreeMap::new(),
+            all: vec![],
+            files: HashSet::new()
+        };
+        for entry in entries.flatten() {
+            let dir = entry.file_name().to_string_lossy().to_string();
+            if !entry.path().is_dir() || dir.starts_with('.') {
+                continue
+            }
+            let mut images = vec![];
+            if let Ok(files) = fs::read_dir(entry.path()) {
+                for file in files.flatten() {
+                    let name = file.file_name().to_string_lossy().to_string();
+                    if file.path().is_file() && content_type(&name).is_some() {
+                        images.push(format!("{}/{}", dir, name));
+                    }
+                }
+            }
+            images.sort();
+            backend.all.extend(images.iter().cloned());
+            backend.files.extend(images.iter().cloned());
+            let (breed, sub_breed) = match dir.split_once('-') {
+                Some((breed, sub_breed)) => (breed.to_string(), Some(sub_breed.to_string())),
+                None => (dir.clone(), None)
+            };
+            let entry = backend.breeds.entry(breed).or_default();
+            match sub_breed {
+                Some(sub_breed) => entry.sub_breeds.entry(sub_breed).or_default().extend(images),
+                None => entry.images.extend(images)
+            }
+        }
+        Ok(backend)
+    }
+
+    fn paths(&self, breed: &str, sub_breed: Option<&str>) -> Result<Vec<&String>, BackendError> {
+        let entry = match self.breeds.get(breed) {
+            Some(entry) => entry,
+            None => return Err(BackendError::breed_not_found())
+        };
+        match sub_breed {
+            Some(sub_breed) => match entry.sub_breeds.get(sub_breed) {
+                Some(images) => Ok(images.iter().collect()),
+                None => Err(BackendError::sub_breed_not_found())
+            },
+            None => Ok(entry.images.iter().chain(entry.sub_breeds.values().flatten()).collect())
+        }
+    }
+}
+
+impl Backend for DirectoryBackend {
+    fn breeds(&self) -> Result<BTreeMap<String, Vec<String>>, BackendError> {
+        Ok(self.breeds.iter().map(|(name, breed)| (name.clone(), breed.sub_breeds.keys().cloned().collect())).collect())
+    }
+
+    fn images(&self, base: &str, breed: &str, sub_breed: Option<&str>) -> Result<Vec<String>, BackendError> {
+        Ok(self.paths(breed, sub_breed)?.iter().map(|image| url(base, image)).collect())
+    }
+
+    fn random(&self, base: &str, breed: Option<&str>, sub_breed: Option<&str>, count: usize) -> Result<Vec<String>, BackendError> {
+        let paths = match breed {
+            Some(breed) => self.paths(breed, sub_breed)?,
+            None => self.all.iter().collect()
+        };
+        Ok(pick(&paths, count).iter().map(|image| url(base, image)).collect())
+    }
+
+    fn file(&self, dir: &str, file: &str) -> Option<(Vec<u8>, &'static str)> {
+        if !self.files.contains(&format!("{}/{}", dir, file)) {
+            return None
+        }
+        match fs::read(self.root.join("breeds").join(dir).join(file)) {
+            Ok(bytes) => Some((bytes, content_type(file).unwrap_or("application/octet-stream"))),
+            Err(_) => None
+        }
+    }
+}
+
+struct Cache<T> {
+    entries: Mutex<HashMap<String, (Instant, T)>>,
+    flights: Group<Result<T, BackendError>>
+}
+
+impl<T: Clone> Cache<T> {
+    fn new() -> Cache<T> {
+        Cache {
+            entries: Mutex::new(HashMap::new()),
+            flights: Group::new()
+        }
+    }
+
+    fn get<F: FnOnce() -> Result<T, DogAPIError>>(&self, key: &str, ttl: Duration, metrics: &Recorder, fetch: F) -> Result<T, BackendError> {
+        if let Ok(entries) = self.entries.lock() {
+            if let Some((created, value)) = entries.get(key) {
+                if created.elapsed() < ttl {
+                    metrics.record_cache(true);
+                    return Ok(value.clone())
+                }
+            }
+        }
+        metrics.record_cache(false);
+        let (result, _) = self.flights.run(key, || {
+            let value = fetch()?;
+            if let Ok(mut entries) = self.entries.lock() {
+                entries.insert(key.to_string(), (Instant::now(), value.clone()));
+            }
+            Ok(value)
+        });
+        result
+    }
+}
+
+/// Backend answering from a cache in front of another Dog API
+///
+/// The breeds list and image lists are cached for a time-to-live, concurrent identical
+/// cache misses share one upstream request. Random endpoints are forwarded upstream,
+/// or sampled from the cached image lists with [`ProxyBackend::sample_local`].
+pub struct ProxyBackend {
+    client: Client,
+    ttl: Duration,
+    sample_local: bool,
+    metrics: Recorder,
+    breeds: Cache<Arc<BTreeMap<String, Vec<String>>>>,
+    images: Cache<Arc<Vec<String>>>
+}
+
+impl ProxyBackend {
+    /// Creates a proxy
+    ///
+    /// * `client` client of the upstream API, see [`ClientBuilder::base_url`](crate::ClientBuilder::base_url)
+    pub fn new(client: Client) -> ProxyBackend {
+        ProxyBackend {
+            client,
+            ttl: Duration::from_secs(3600),
+            sample_local: false,
+            metrics: Recorder::default(),
+            breeds: Cache::new(),
+            images: Cache::new()
+        }
+    }
+
+    /// Sets how long the breeds list and image lists are cached
+    ///
+    /// * `ttl` time-to-live, defaults to one hour
+    pub fn ttl(mut self, ttl: Duration) -> ProxyBackend {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Answers random endpoints from the cached image lists instead of forwarding them
+    pub fn sample_local(mut self, sample_local: bool) -> ProxyBackend {
+        self.sample_local = sample_local;
+        self
+    }
+
+    /// Reports cache hits and misses into a metrics collector
+    pub fn metrics<M: Metrics + 'static>(mut self, metrics: Arc<M>) -> ProxyBackend {
+        self.metrics = Recorder::new(metrics);
+        self
+    }
+
+    fn catalog(&self) -> Result<Arc<BTreeMap<String, Vec<String>>>, BackendError> {
+        self.breeds.get("breeds", self.ttl, &self.metrics, || {
+            let mut breeds = BTreeMap::new();
+            for (breed, sub_breeds) in self.client.breeds_list()? {
+                let mut sub_breeds = sub_breeds.unwrap_or_default();
+                sub_breeds.sort();
+                breeds.insert(breed, sub_breeds);
+            }
+            Ok(Arc::new(breeds))
+        })
+    }
+
+    fn cached_images(&self, breed: &str, sub_breed: Option<&str>) -> Result<Arc<Vec<String>>, BackendError> {
+        let key = match sub_breed {
+            Some(sub_breed) => format!("{}/{}", breed, sub_breed),
+            None => breed.to_string()
+        };
+        self.images.get(&key, self.ttl, &self.metrics, || match sub_breed {
+            Some(sub_breed) => self.client.images_by_sub_breed(breed, sub_breed).map(Arc::new),
+            None => self.client.images_by_breed(breed).map(Arc::new)
+        })
+    }
+}
+
+impl Backend for ProxyBackend {
+    fn breeds(&self) -> Result<BTreeMap<String, Vec<String>>, BackendError> {
+        Ok(self.catalog()?.as_ref().clone())
+    }
+
+    fn images(&self, _base: &str, breed: &str, sub_breed: Option<&str>) -> Result<Vec<String>, BackendError> {
+        Ok(self.cached_images(breed, sub_breed)?.as_ref().clone())
+    }
+
+    fn random(&self, _base: &str, breed: Option<&str>, sub_breed: Option<&str>, count: usize) -> Result<Vec<String>, BackendError> {
+        if !self.sample_local {
+            let images = match (breed, sub_breed) {
+                (Some(breed), Some(sub_breed)) => self.client.multiple_random_images_by_sub_breed(breed, sub_breed, count as i64)?,
+                (Some(breed), None) => self.client.multiple_random_images_by_breed(breed, count as i64)?,
+                _ => self.client.multiple_random_images(count.min(MAX_IMAGES) as i8)?
+            };
+            return Ok(images)
+        }
+        if let Some(breed) = breed {
+            let images = self.cached_images(breed, sub_breed)?;
+            let images: Vec<&String> = images.iter().collect();
+            return Ok(pick(&images, count).into_iter().cloned().collect())
+        }
+        let catalog = self.catalog()?;
+        let breeds: Vec<(&String, &Vec<String>)> = catalog.iter().collect();
+        let mut images = vec![];
+        for _ in 0..count {
+            if let Some((breed, sub_breeds)) = pick(&breeds, 1).first() {
+                let sub_breed = pick(&sub_breeds.iter().collect::<Vec<_>>(), 1).first().map(|sub_breed| sub_breed.as_str());
+                let list = self.cached_images(breed, sub_breed)?;
+                if let Some(image) = pick(&list.iter().collect::<Vec<_>>(), 1).first() {
+                    images.push(image.to_string());
+                }
+            }
+        }
+        Ok(images)
+    }
+}
+
+/// Dog API compatible server
+#[derive(Clone)]
+pub struct Server {
+    backend: Arc<dyn Backend>,
+    public_url: Option<String>,
+    metrics: Option<Arc<PrometheusMetrics>>
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    host: Option<String>
+}
+
+struct HttpResponse {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>
+}
+
+impl Server {
+    /// Creates a server for a backend
+    pub fn new<B: Backend + 'static>(backend: B) -> Server {
+        Server {
+            backend: Arc::new(backend),
+            public_url: None,
+            metrics: None
+        }
+    }
+
+    /// Creates a server for a directory
+    ///
+    /// * `root` directory containing the `breeds` directory
+    ///
+    /// The directory is scanned once, restart the server to pick up new images
+    pub fn from_dir<P: AsRef<Path>>(root: P) -> Result<Server, DogAPIError> {
+        Ok(Server::new(DirectoryBackend::new(root)?))
+    }
+
+    /// Sets the url the image links point at
+    ///
+    /// * `url` public url of the server, defaults to `http://<Host header>`
+    pub fn public_url(mut self, url: &str) -> Server {
+        self.public_url = Some(url.trim().trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Serves a metrics collector at `/metrics`
+    pub fn metrics(mut self, metrics: Arc<PrometheusMetrics>) -> Server {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Listens on an address and serves requests until the process exits
+    ///
+    /// * `addr` address to listen on, e.g. `127.0.0.1:8080`
+    pub fn serve<A: ToSocketAddrs>(self, addr: A) -> Result<(), DogAPIError> {
+        match TcpListener::bind(addr) {
+            Ok(listener) => self.run(listener),
+            Err(err) => Err(DogAPIError::Error(format!("Something went wrong while binding the address: {}", err)))
+        }
+    }
+
+    /// Serves requests from a bound listener until the process exits
+    pub fn run(self, listener: TcpListener) -> Result<(), DogAPIError> {
+        let connections = Arc::new(AtomicUsize::new(0));
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                        connections.fetch_sub(1, Ordering::SeqCst);
+                        continue
+                    }
+                    let server = self.clone();
+                    let connections = connections.clone();
+                    thread::spawn(move || {
+                        server.connection(stream);
+                        connections.fetch_sub(1, Ordering::SeqCst);
+                    });
+                },
+                Err(err) => return Err(DogAPIError::Error(format!("Something went wrong while accepting a connection: {}", err)))
+            }
+        }
+        Ok(())
+    }
+
+    fn connection(&self, stream: TcpStream) {
+        if stream.set_read_timeout(Some(TIMEOUT)).is_err() || stream.set_write_timeout(Some(TIMEOUT)).is_err() {
+            return
+        }
+        let mut reader = match stream.try_clone() {
+            Ok(stream) => BufReader::new(stream),
+            Err(_) => return
+        };
+        let mut writer = stream;
+        while let Some(request) = read_request(&mut reader) {
+            let response = self.handle(&request);
+            if write_response(&mut writer, &request, &response).is_err() {
+                return
+            }
+        }
+    }
+
+    fn handle(&self, request: &HttpRequest) -> HttpResponse {
+        if request.method != "GET" && request.method != "HEAD" {
+            return error(405, &format!("No route found for \"{} {}\": Method Not Allowed", request.method, request.path))
+        }
+        let path = request.path.split('?').next().unwrap_or_default();
+        let segments: Vec<String> = path.trim_matches('/').split('/').map(decode).collect();
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        let base = match self.public_url {
+            Some(ref url) => url.clone(),
+            None => format!("http://{}", request.host.as_deref().unwrap_or("localhost"))
+        };
+        let backend = self.backend.as_ref();
+        let result = match segments.as_slice() {
+            ["api", "breeds", "list", "all"] => backend.breeds().map(|breeds| json!(breeds)),
+            ["api", "breeds", "image", "random"] => single(backend.random(&base, None, None, 1)),
+            ["api", "breeds", "image", "random", count] => backend.random(&base, None, None, parse_count(count)).map(|images| json!(images)),
+            ["api", "breed", breed, "list"] => backend.breeds().and_then(|breeds| match breeds.get(*breed) {
+                Some(sub_breeds) => Ok(json!(sub_breeds)),
+                None => Err(BackendError::breed_not_found())
+            }),
+            ["api", "breed", breed, "images"] => backend.images(&base, breed, None).map(|images| json!(images)),
+            ["api", "breed", breed, "images", "random"] => single(backend.random(&base, Some(breed), None, 1)),
+            ["api", "breed", breed, "images", "random", count] => backend.random(&base, Some(breed), None, parse_count(count)).map(|images| json!(images)),
+            ["api", "breed", breed, sub_breed, "images"] => backend.images(&base, breed, Some(sub_breed)).map(|images| json!(images)),
+            ["api", "breed", breed, sub_breed, "images", "random"] => single(backend.random(&base, Some(breed), Some(sub_breed), 1)),
+            ["api", "breed", breed, sub_breed, "images", "random", count] => backend.random(&base, Some(breed), Some(sub_breed), parse_count(count)).map(|images| json!(images)),
+            ["breeds", dir, file] => return match backend.file(dir, file) {
+                Some((body, content_type)) => HttpResponse { status: 200, content_type, body },
+                None => no_route(request)
+            },
+            ["metrics"] if self.metrics.is_some() => return HttpResponse {
+                status: 200,
+                content_type: "text/plain; version=0.0.4",
+                body: self.metrics.as_ref().map(|metrics| metrics.render()).unwrap_or_default().into_bytes()
+            },
+            _ => return no_route(request)
+        };
+        match result {
+            Ok(message) => success(message),
+            Err(err) => error(err.code, &err.message)
+        }
+    }
+}
+
+impl Debug for Server {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "Server {{ public_url: {:?} }}", self.public_url)
+    }
+}
+
+impl Debug for ProxyBackend {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "ProxyBackend {{ ttl: {:?}, sample_local: {} }}", self.ttl, self.sample_local)
+    }
+}
+
+fn single(images: Result<Vec<String>, BackendError>) -> Result<Value, BackendError> {
+    match images?.into_iter().next() {
+        Some(image) => Ok(json!(image)),
+        None => Err(BackendError::new(404, "No images found"))
+    }
+}
+
+fn success(message: Value) -> HttpResponse {
+    HttpResponse {
+        status: 200,
+        content_type: "application/json",
+        body: json!({ "message": message, "status": "success" }).to_string().into_bytes()
+    }
+}
+
+fn error(code: u16, message: &str) -> HttpResponse {
+    HttpResponse {
+        status: code,
+        content_type: "application/json",
+        body: json!({ "status": "error", "message": message, "code": code }).to_string().into_bytes()
+    }
+}
+
+fn no_route(request: &HttpRequest) -> HttpResponse {
+    error(404, &format!("No route found for \"{} {}\" with code: 0", request.method, request.path))
+}
+
+fn url(base: &str, image: &str) -> String {
+    format!("{}/breeds/{}", base, image)
+}
+
+fn parse_count(count: &str) -> usize {
+    count.parse::<i64>().unwrap_or(1).clamp(1, MAX_IMAGES as i64) as usize
+}
+
+fn content_type(file: &str) -> Option<&'static str> {
+    let extension = file.rsplit_once('.').map(|(_, extension)| extension.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("jpg") | Some("jpeg") => Some("image/jpeg"),
+        Some("png") => Some("image/png"),
+        Some("gif") => Some("image/gif"),
+        Some("webp") => Some("image/webp"),
+        _ => None
+    }
+}
+
+fn decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Some(byte) = std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn random(bound: usize) -> usize {
+    thread_local! {
+        static STATE: Cell<u64> = Cell::new(RandomState::new().build_hasher().finish() | 1);
+    }
+    STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        state.set(x);
+        (x % bound.max(1) as u64) as usize
+    })
+}
+
+fn pick<T: Copy>(items: &[T], count: usize) -> Vec<T> {
+    let mut indexes: Vec<usize> = (0..items.len()).collect();
+    let count = count.min(items.len());
+    for i in 0..count {
+        let j = i + random(items.len() - i);
+        indexes.swap(i, j);
+    }
+    indexes[..count].iter().map(|i| items[*i]).collect()
+}
+
+fn read_request(reader: &mut BufReader<TcpStream>) -> Option<HttpRequest> {
+    // Blank lines may separate pipelined requests
+    let mut line = read_line(reader)?;
+    for _ in 0..MAX_HEADERS {
+        if !line.trim().is_empty() {
+            break
+        }
+        line = read_line(reader)?;
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+    let mut host = None;
+    for _ in 0..=MAX_HEADERS {
+        let header = read_line(reader)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            return Some(HttpRequest { method, path, host })
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Reads a line of at most `MAX_LINE` bytes, `None` at EOF or when it is longer
+fn read_line(reader: &mut BufReader<TcpStream>) -> Option<String> {
+    let mut line = String::new();
+    let read = reader.by_ref().take(MAX_LINE).read_line(&mut line).ok()?;
+    if read == 0 || !line.ends_with('\n') {
+        return None
+    }
+    Some(line)
+}
+
+fn write_response(writer: &mut TcpStream, request: &HttpRequest, response: &HttpResponse) -> std::io::Result<()> {
+    let reason = match response.status {
+        200 => "OK",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        502 => "Bad Gateway",
+        _ => "Internal Server Error"
+    };
+    write!(writer, "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\n\r\n",
+           response.status, reason, response.content_type, response.body.len())?;
+    if request.method != "HEAD" {
+        writer.write_all(&response.body)?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{client, serve, TempDir};
+
+    fn dogs() -> TempDir {
+        let dir = TempDir::new();
+        dir.write("breeds/affenpinscher/a1.jpg", b"affenpinscher 1");
+        dir.write("breeds/affenpinscher/a2.jpg", b"affenpinscher 2");
+        dir.write("breeds/hound-afghan/h1.jpg", b"afghan 1");
+        dir.write("breeds/hound-afghan/h2.jpg", b"afghan 2");
+        dir.write("breeds/hound-basset/h3.jpg", b"basset 1");
+        dir
+    }
+
+    #[test]
+    fn client_round_trip() {
+        let dir = dogs();
+        let base_url = serve(dir.path());
+        let client = client(&base_url);
+        let host = base_url.trim_end_matches("/api");
+        let image = |path: &str| format!("{}/breeds/{}", host, path);
+
+        let breeds = client.breeds_list().unwrap();
+        assert_eq!(breeds.len(), 2);
+        assert_eq!(breeds["affenpinscher"], None);
+        assert_eq!(breeds["hound"], Some(vec!["afghan".to_string(), "basset".to_string()]));
+        assert_eq!(client.sub_breeds_list("hound").unwrap(), Some(vec!["afghan".to_string(), "basset".to_string()]));
+        assert_eq!(client.sub_breeds_list("affenpinscher").unwrap(), None);
+
+        let affenpinscher = vec![image("affenpinscher/a1.jpg"), image("affenpinscher/a2.jpg")];
+        let afghan = vec![image("hound-afghan/h1.jpg"), image("hound-afghan/h2.jpg")];
+        assert_eq!(client.images_by_breed("affenpinscher").unwrap(), affenpinscher);
+        assert_eq!(client.images_by_breed("hound").unwrap().len(), 3);
+        assert_eq!(client.images_by_sub_breed("hound", "afghan").unwrap(), afghan);
+        assert_eq!(client.images_by_breed_iter("affenpinscher").unwrap().collect::<Result<Vec<_>, _>>().unwrap(), affenpinscher);
+        assert_eq!(client.images_by_sub_breed_iter("hound", "afghan").unwrap().collect::<Result<Vec<_>, _>>().unwrap(), afghan);
+
+        assert!(client.random_image().unwrap().starts_with(&image("")));
+        assert_eq!(client.multiple_random_images(3).unwrap().len(), 3);
+        assert!(affenpinscher.contains(&client.random_image_by_breed("affenpinscher").unwrap()));
+        assert_eq!(client.multiple_random_images_by_breed("affenpinscher", 5).unwrap().len(), 2);
+        assert!(afghan.contains(&client.random_image_by_sub_breed("hound", "afghan").unwrap()));
+        let random = client.multiple_random_images_by_sub_breed("hound", "afghan", 1).unwrap();
+        assert_eq!(random.len(), 1);
+        assert!(afghan.contains(&random[0]));
+
+        assert_eq!(client.download_image(&afghan[0]).unwrap(), b"afghan 1");
+
+        let err = client.images_by_breed("poodle").unwrap_err();
+        assert_eq!(err.code(), Some(404));
+        assert_eq!(err.status(), Some(404));
+        assert_eq!(err.to_string(), "Breed not found (master breed does not exist)");
+        let err = client.images_by_sub_breed("hound", "poodle").unwrap_err();
+        assert_eq!(err.code(), Some(404));
+        assert!(client.download_image(&image("hound-afghan/missing.jpg")).is_err());
+    }
+
+    #[test]
+    fn pipelined_requests_with_blank_lines() {
+        let dir = dogs();
+        let base_url = serve(dir.path());
+        let addr = base_url.trim_start_matches("http://").trim_end_matches("/api");
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let request = "GET /api/breed/hound/list HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        stream.write_all(format!("{}\r\n{}", request, request).as_bytes()).unwrap();
+        let mut received = String::new();
+        let mut buffer = [0; 4096];
+        while received.matches("\"status\":\"success\"").count() < 2 {
+            let n = stream.read(&mut buffer).unwrap();
+            assert!(n > 0, "connection closed after {:?}", received);
+            received.push_str(&String::from_utf8_lossy(&buffer[..n]));
+        }
+        assert_eq!(received.matches("HTTP/1.1 200 OK").count(), 2);
+    }
+
+    #[test]
+    fn rejects_oversized_lines() {
+        let dir = dogs();
+        let base_url = serve(dir.path());
+        let addr = base_url.trim_start_matches("http://").trim_end_matches("/api");
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let _ = stream.write_all(format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE as usize)).as_bytes());
+        let mut received = vec![];
+        let _ = stream.read_to_end(&mut received);
+        assert!(received.is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+enum State<T> {
+    Pending,
+    Done(T),
+    Abandoned
+}
+
+struct Call<T> {
+    state: Mutex<State<T>>,
+    done: Condvar
+}
+
+/// Deduplicates concurrent calls sharing the same key
+///
+/// The first caller of a key runs the function, callers arriving while it runs
+/// wait for it and receive a clone of its result.
+pub(crate) struct Group<T> {
+    calls: Mutex<HashMap<String, Arc<Call<T>>>>
+}
+
+struct Leader<'a, T> {
+    group: &'a Group<T>,
+    key: &'a str,
+    call: Arc<Call<T>>
+}
+
+impl<T: Clone> Group<T> {
+    pub(crate) fn new() -> Group<T> {
+        Group {
+            calls: Mutex::new(HashMap::new())
+        }
+    }
+
+    /// Runs `f` for `key` unless a call for `key` is in flight
+    ///
+    /// Returns the result and whether it was shared from another caller
+    pub(crate) fn run<F: FnOnce() -> T>(&self, key: &str, f: F) -> (T, bool) {
+        let mut f = Some(f);
+        loop {
+            let (call, leader) = {
+                let mut calls = lock(&self.calls);
+                match calls.get(key) {
+                    Some(call) => (call.clone(), false),
+                    None => {
+                        let call = Arc::new(Call {
+                            state: Mutex::new(State::Pending),
+                            done: Condvar::new()
+                        });
+                        calls.insert(key.to_string(), call.clone());
+                        (call, true)
+                    }
+                }
+            };
+            if leader {
+                let leader = Leader { group: self, key, call };
+                let result = f.take().expect("single-flight function already ran")();
+                *lock(&leader.call.state) = State::Done(result.clone());
+                return (result, false)
+            }
+            let mut state = lock(&call.state);
+            while let State::Pending = *state {
+                state = match call.done.wait(state) {
+                    Ok(state) => state,
+                    Err(poisoned) => poisoned.into_inner()
+                };
+            }
+            if let State::Done(ref result) = *state {
+                return (result.clone(), true)
+            }
+        }
+    }
+}
+
+impl<T> Drop for Leader<'_, T> {
+    fn drop(&mut self) {
+        let mut calls = lock(&self.group.calls);
+        calls.remove(self.key);
+        let mut state = lock(&self.call.state);
+        if let State::Pending = *state {
+            *state = State::Abandoned
+        }
+        self.call.done.notify_all();
+    }
+}
+
+impl<T> Debug for Group<T> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "Group({} in flight)", lock(&self.calls).len())
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    /// Blocks until `waiters` callers besides the leader hold the in-flight call of `key`
+    fn wait_for_waiters<T>(group: &Group<T>, key: &str, waiters: usize) {
+        loop {
+            if let Some(call) = lock(&group.calls).get(key) {
+                // The map, the leader and each waiter hold a reference
+                if Arc::strong_count(call) >= 2 + waiters {
+                    return
+                }
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn single_caller_runs_the_function() {
+        let group = Group::new();
+        assert_eq!(group.run("a", || 1), (1, false));
+        assert_eq!(group.run("a", || 2), (2, false));
+        assert!(lock(&group.calls).is_empty());
+    }
+
+    #[test]
+    fn concurrent_callers_share_the_result() {
+        let group = &Group::new();
+        let runs = &AtomicUsize::new(0);
+        let (release, released) = mpsc::channel::<()>();
+        thread::scope(|scope| {
+            let leader = scope.spawn(move || group.run("a", || {
+                runs.fetch_add(1, Ordering::SeqCst);
+                released.recv().unwrap();
+                "leader"
+            }));
+            while lock(&group.calls).is_empty() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            let waiters: Vec<_> = (0..3).map(|_| scope.spawn(|| group.run("a", || {
+                runs.fetch_add(1, Ordering::SeqCst);
+                "waiter"
+            }))).collect();
+            wait_for_waiters(group, "a", 3);
+            release.send(()).unwrap();
+            assert_eq!(leader.join().unwrap(), ("leader", false));
+            for waiter in waiters {
+                assert_eq!(waiter.join().unwrap(), ("leader", true));
+            }
+        });
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert!(lock(&group.calls).is_empty());
+    }
+
+    #[test]
+    fn different_keys_do_not_share() {
+        let group = &Group::new();
+        let (release, released) = mpsc::channel::<()>();
+        thread::scope(|scope| {
+            let leader = scope.spawn(move || group.run("a", || {
+                released.recv().unwrap();
+                1
+            }));
+            while lock(&group.calls).is_empty() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            assert_eq!(group.run("b", || 2), (2, false));
+            release.send(()).unwrap();
+            assert_eq!(leader.join().unwrap(), (1, false));
+        });
+    }
+
+    #[test]
+    fn waiters_retry_when_the_leader_panics() {
+        let group = &Group::new();
+        let (release, released) = mpsc::channel::<()>();
+        thread::scope(|scope| {
+            let leader = scope.spawn(move || panic::catch_unwind(AssertUnwindSafe(|| group.run("a", || -> u32 {
+                released.recv().unwrap();
+                panic!("leader failed")
+            }))));
+            while lock(&group.calls).is_empty() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            let waiter = scope.spawn(|| group.run("a", || 2));
+            wait_for_waiters(group, "a", 1);
+            release.send(()).unwrap();
+            assert!(leader.join().unwrap().is_err());
+            assert_eq!(waiter.join().unwrap(), (2, false));
+        });
+        assert!(lock(&group.calls).is_empty());
+        assert_eq!(group.run("a", || 3), (3, false));
+    }
+}
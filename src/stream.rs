@@ -0,0 +1,771 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! Streaming image lists
+//!
+//! Decodes the `message` array of a response while it is received, without
+//! buffering the body or building a `serde_json::Value`.
+use std::collections::VecDeque;
+use std::fmt::{Debug, Formatter};
+use std::io::Read;
+use crate::error::DogAPIError;
+use crate::metrics::Recorder;
+use crate::parse::Parsing;
+
+enum Token {
+    BeginObject,
+    EndObject,
+    BeginArray,
+    EndArray,
+    Colon,
+    Comma,
+    String(Vec<u8>),
+    Literal(Vec<u8>)
+}
+
+/// Next token accepted inside an object or array
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    /// After `{`
+    KeyOrEnd,
+    /// After `,` in an object
+    Key,
+    Colon,
+    /// After `:` in an object or `,` in an array
+    Value,
+    /// After `[`
+    ValueOrEnd,
+    CommaOrEnd
+}
+
+/// Object or array being parsed
+struct Frame {
+    array: bool,
+    expect: Expect,
+    /// Whether this is the `message` array of the envelope
+    message: bool
+}
+
+#[derive(Default)]
+enum Lexer {
+    #[default]
+    Between,
+    String { raw: Vec<u8>, escaped: bool },
+    Literal { raw: Vec<u8> }
+}
+
+/// Incremental parser of a `{"message": [...], "status": "..."}` envelope
+#[derive(Default)]
+pub(crate) struct Parser {
+    parsing: Parsing,
+    index: usize,
+    skipped: usize,
+    lexer: Lexer,
+    stack: Vec<Frame>,
+    key: Option<String>,
+    array_seen: bool,
+    status: Option<String>,
+    text: Option<String>,
+    code: Option<u16>,
+    http_status: Option<u16>,
+    done: bool,
+    failed: bool,
+    items: VecDeque<Result<String, DogAPIError>>
+}
+
+impl Parser {
+    pub(crate) fn new(parsing: Parsing) -> Parser {
+        Parser {
+            parsing,
+            ..Parser::default()
+        }
+    }
+
+    /// Sets the HTTP status of the response, reported on API errors
+    pub(crate) fn http_status(&mut self, status: u16) {
+        self.http_status = Some(status)
+    }
+
+    /// Returns the number of malformed entries skipped so far
+    pub(crate) fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Parses the next chunk of the body
+    pub(crate) fn feed(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            if self.failed {
+                return
+            }
+            if let Err(err) = self.byte(*byte) {
+                self.fail(err)
+            }
+        }
+    }
+
+    /// Signals the end of the body
+    pub(crate) fn finish(&mut self) {
+        if self.failed {
+            return
+        }
+        if let Lexer::Literal { ref mut raw } = self.lexer {
+            let raw = std::mem::take(raw);
+            self.lexer = Lexer::Between;
+            if let Err(err) = self.token(Token::Literal(raw)) {
+                return self.fail(err)
+            }
+        }
+        if !self.done {
+            return self.fail(json_error("unexpected end of the body"))
+        }
+        match self.status.as_deref() {
+            Some("success") if self.array_seen => {},
+            Some("success") | None => self.fail(json_error("missing message array")),
+            Some(_) => {
+                let message = self.text.take().unwrap_or_else(|| String::from("Something went wrong while reading json"));
+                self.fail(DogAPIError::Api {
+                    message,
+                    code: self.code,
+                    status: self.http_status
+                })
+            }
+        }
+    }
+
+    /// Returns the next decoded url or error
+    pub(crate) fn pop(&mut self) -> Option<Result<String, DogAPIError>> {
+        self.items.pop_front()
+    }
+
+    fn fail(&mut self, err: DogAPIError) {
+        self.failed = true;
+        self.items.push_back(Err(err));
+    }
+
+    fn malformed(&mut self) -> Result<(), DogAPIError> {
+        if let Some(err) = self.parsing.malformed(&format!("message[{}]", self.index), "is not a string") {
+            return Err(err)
+        }
+        self.index += 1;
+        self.skipped += 1;
+        Ok(())
+    }
+
+    fn byte(&mut self, byte: u8) -> Result<(), DogAPIError> {
+        match self.lexer {
+            Lexer::String { ref mut raw, ref mut escaped } => {
+                raw.push(byte);
+                if *escaped {
+                    *escaped = false;
+                } else if byte == b'\\' {
+                    *escaped = true;
+                } else if byte == b'"' {
+                    let raw = std::mem::take(raw);
+                    self.lexer = Lexer::Between;
+                    return self.token(Token::String(raw))
+                }
+                Ok(())
+            },
+            Lexer::Literal { ref mut raw } if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'+' | b'.') => {
+                raw.push(byte);
+                Ok(())
+            },
+            Lexer::Literal { ref mut raw } => {
+                let raw = std::mem::take(raw);
+                self.lexer = Lexer::Between;
+                self.token(Token::Literal(raw))?;
+                self.byte(byte)
+            },
+            Lexer::Between => match byte {
+                b' ' | b'\t' | b'\n' | b'\r' => Ok(()),
+                b'{' => self.token(Token::BeginObject),
+                b'}' => self.token(Token::EndObject),
+                b'[' => self.token(Token::BeginArray),
+                b']' => self.token(Token::EndArray),
+                b':' => self.token(Token::Colon),
+                b',' => self.token(Token::Comma),
+                b'"' => {
+                    self.lexer = Lexer::String { raw: vec![b'"'], escaped: false };
+                    Ok(())
+                },
+                b'-' | b'0'..=b'9' | b't' | b'f' | b'n' => {
+                    self.lexer = Lexer::Literal { raw: vec![byte] };
+                    Ok(())
+                },
+                _ => Err(json_error(&format!("unexpected character '{}'", byte as char)))
+            }
+        }
+    }
+
+    fn token(&mut self, token: Token) -> Result<(), DogAPIError> {
+        if self.done {
+            return Err(json_error("trailing characters"))
+        }
+        let expect = self.stack.last().map(|frame| (frame.array, frame.expect));
+        match (token, expect) {
+            (Token::Colon, Some((false, Expect::Colon))) => self.expect(Expect::Value),
+            (Token::Colon, _) => Err(json_error("unexpected ':'")),
+            (Token::Comma, Some((array, Expect::CommaOrEnd))) => self.expect(if array { Expect::Value } else { Expect::Key }),
+            (Token::Comma, _) => Err(json_error("unexpected ','")),
+            (Token::EndObject, Some((false, Expect::KeyOrEnd))) | (Token::EndObject, Some((false, Expect::CommaOrEnd))) => self.close(),
+            (Token::EndObject, _) => Err(json_error("unexpected '}'")),
+            (Token::EndArray, Some((true, Expect::ValueOrEnd))) | (Token::EndArray, Some((true, Expect::CommaOrEnd))) => self.close(),
+            (Token::EndArray, _) => Err(json_error("unexpected ']'")),
+            (Token::String(raw), Some((false, Expect::KeyOrEnd))) | (Token::String(raw), Some((false, Expect::Key))) => {
+                let key = decode(&raw)?;
+                if self.stack.len() == 1 {
+                    self.key = Some(key);
+                }
+                self.expect(Expect::Colon)
+            },
+            (token, None) => match token {
+                Token::BeginObject => {
+                    self.stack.push(Frame { array: false, expect: Expect::KeyOrEnd, message: false });
+                    Ok(())
+                },
+                _ => Err(json_error("expected an object"))
+            },
+            (token, Some((_, Expect::Value))) | (token, Some((_, Expect::ValueOrEnd))) => {
+                self.expect(Expect::CommaOrEnd)?;
+                self.value(token)
+            },
+            (_, Some((_, Expect::CommaOrEnd))) => Err(json_error("expected ',' between values")),
+            (_, Some((_, Expect::Colon))) => Err(json_error("expected ':' after a key")),
+            (_, Some((_, Expect::KeyOrEnd))) | (_, Some((_, Expect::Key))) => Err(json_error("expected a key"))
+        }
+    }
+
+    /// Handles a value at the current position, the enclosing frame already expects what follows it
+    fn value(&mut self, token: Token) -> Result<(), DogAPIError> {
+        let depth = self.stack.len();
+        let in_message = depth == 2 && self.stack.last().is_some_and(|frame| frame.message);
+        match token {
+            Token::String(raw) if in_message => {
+                let url = decode(&raw)?;
+                self.index += 1;
+                self.items.push_back(Ok(url));
+                Ok(())
+            },
+            Token::String(raw) => {
+                if depth == 1 {
+                    match self.key.as_deref() {
+                        Some("status") => self.status = Some(decode(&raw)?),
+                        Some("message") => self.text = Some(decode(&raw)?),
+                        _ => {
+                            decode(&raw)?;
+                        }
+                    }
+                } else {
+                    decode(&raw)?;
+                }
+                Ok(())
+            },
+            Token::Literal(raw) => {
+                literal(&raw)?;
+                if in_message {
+                    return self.malformed()
+                }
+                if depth == 1 && self.key.as_deref() == Some("code") {
+                    self.code = std::str::from_utf8(&raw).ok().and_then(|code| code.parse().ok());
+                }
+                Ok(())
+            },
+            Token::BeginObject | Token::BeginArray => {
+                if in_message {
+                    self.malformed()?;
+                }
+                let array = matches!(token, Token::BeginArray);
+                let message = array && depth == 1 && self.key.as_deref() == Some("message");
+                if message {
+                    self.array_seen = true;
+                }
+                self.stack.push(Frame {
+                    array,
+                    expect: if array { Expect::ValueOrEnd } else { Expect::KeyOrEnd },
+                    message
+                });
+                Ok(())
+            },
+            Token::EndObject | Token::EndArray | Token::Colon | Token::Comma => Err(json_error("expected a value"))
+        }
+    }
+
+    fn expect(&mut self, expect: Expect) -> Result<(), DogAPIError> {
+        if let Some(frame) = self.stack.last_mut() {
+            frame.expect = expect;
+        }
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), DogAPIError> {
+        self.stack.pop();
+        if self.stack.is_empty() {
+            self.done = true;
+        }
+        Ok(())
+    }
+}
+
+/// Checks that a literal is `true`, `false`, `null` or a number
+fn literal(raw: &[u8]) -> Result<(), DogAPIError> {
+    match serde_json::from_slice::<serde_json::Value>(raw) {
+        Ok(value) if !value.is_string() => Ok(()),
+        _ => Err(json_error(&format!("invalid literal '{}'", String::from_utf8_lossy(raw))))
+    }
+}
+
+fn decode(raw: &[u8]) -> Result<String, DogAPIError> {
+    match serde_json::from_slice(raw) {
+        Ok(value) => Ok(value),
+        Err(err) => Err(DogAPIError::Error(format!("Something went wrong while reading json: {}", err)))
+    }
+}
+
+fn json_error(reason: &str) -> DogAPIError {
+    DogAPIError::Error(format!("Something went wrong while reading json: {}", reason))
+}
+
+/// Iterator over the urls of a streamed image list
+///
+/// Created by [`Client::images_by_breed_iter`](crate::Client::images_by_breed_iter) and
+/// [`Client::images_by_sub_breed_iter`](crate::Client::images_by_sub_breed_iter).
+/// An error ends the iteration.
+pub struct ImageIter {
+    reader: Box<dyn Read + Send>,
+    parser: Parser,
+    buffer: Vec<u8>,
+    finished: bool,
+    name: &'static str,
+    bytes: u64,
+    limit: u64,
+    metrics: Recorder,
+    parsing: Parsing
+}
+
+impl ImageIter {
+    pub(crate) fn new(reader: Box<dyn Read + Send>, name: &'static str, status: u16, limit: u64, metrics: Recorder, parsing: Parsing) -> ImageIter {
+        let mut parser = Parser::new(parsing.clone());
+        parser.http_status(status);
+        ImageIter {
+            reader,
+            parser,
+            buffer: vec![0; 8192],
+            finished: false,
+            name,
+            bytes: 0,
+            limit,
+            metrics,
+            parsing
+        }
+    }
+
+    /// Returns the number of malformed entries skipped so far in [`ParseMode::Lenient`](crate::parse::ParseMode::Lenient)
+    pub fn skipped(&self) -> usize {
+        self.parser.skipped()
+    }
+}
+
+impl Iterator for ImageIter {
+    type Item = Result<String, DogAPIError>;
+
+    fn next(&mut self) -> Option<Result<String, DogAPIError>> {
+        loop {
+            if let Some(item) = self.parser.pop() {
+                if item.is_err() {
+                    self.finished = true;
+                }
+                return Some(item)
+            }
+            if self.finished {
+                return None
+            }
+            match self.reader.read(&mut self.buffer) {
+                Ok(0) => {
+                    self.finished = true;
+                    self.metrics.record_bytes(self.name, self.bytes);
+                    self.parser.finish();
+                    self.parsing.skipped(self.name, self.parser.skipped());
+                },
+                Ok(n) => {
+                    self.bytes += n as u64;
+                    if self.bytes > self.limit {
+                        self.finished = true;
+                        return Some(Err(DogAPIError::ResponseTooLarge(self.limit)))
+                    }
+                    self.parser.feed(&self.buffer[..n]);
+                },
+                Err(err) => {
+                    self.finished = true;
+                    return Some(Err(DogAPIError::Error(format!("Something went wrong while reading bytes: {}", err))))
+                }
+            }
+        }
+    }
+}
+
+impl Debug for ImageIter {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "ImageIter {{ bytes: {}, finished: {} }}", self.bytes, self.finished)
+    }
+}
+
+#[cfg(feature = "stream")]
+pub use self::r#async::ImageStream;
+
+#[cfg(feature = "stream")]
+mod r#async {
+    use std::fmt::Display;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+    use crate::client::MAX_RESPONSE_SIZE;
+    use crate::error::DogAPIError;
+    use futures_core::Stream;
+    use crate::parse::ParseMode;
+    use crate::parse::ParseWarning;
+    use crate::parse::Parsing;
+    use super::Parser;
+
+    /// Async stream over the urls of an image list
+    ///
+    /// Wraps any stream of body chunks of a request to `breed/<breed>/images`, e.g. the
+    /// `bytes_stream` of a response from an async `reqwest` client. An error ends the stream.
+    pub struct ImageStream<S> {
+        chunks: S,
+        parser: Parser,
+        parsing: Parsing,
+        status: u16,
+        finished: bool,
+        bytes: u64,
+        limit: u64
+    }
+
+    impl<S> ImageStream<S> {
+        /// Creates a stream from a stream of body chunks
+        ///
+        /// * `chunks` body of the response
+        /// * `status` HTTP status of the response, reported by the errors of the API
+        pub fn new(chunks: S, status: u16) -> ImageStream<S> {
+            let parsing = Parsing::default();
+            ImageStream {
+                chunks,
+                parser: parser(&parsing, status),
+                parsing,
+                status,
+                finished: false,
+                bytes: 0,
+                limit: MAX_RESPONSE_SIZE
+            }
+        }
+
+        /// Sets the maximum size of the body
+        ///
+        /// * `bytes` maximum body size, defaults to 16 MiB like
+        ///   [`ClientBuilder::max_response_size`](crate::ClientBuilder::max_response_size)
+        ///
+        /// Larger bodies fail with [`DogAPIError::ResponseTooLarge`]
+        pub fn max_size(mut self, bytes: u64) -> ImageStream<S> {
+            self.limit = bytes;
+            self
+        }
+
+        /// Sets how malformed entries of the list are handled, defaults to [`ParseMode::Lenient`]
+        pub fn parse_mode(mut self, mode: ParseMode) -> ImageStream<S> {
+            self.parsing.mode = mode;
+            self.parser = parser(&self.parsing, self.status);
+            self
+        }
+
+        /// Sets a callback for the entries skipped in lenient mode
+        ///
+        /// * `on_warning` called once at the end of the stream if entries were skipped,
+        ///   with `image_stream` as the endpoint
+        ///
+        /// *NOTE* ~ Skipped entries are also logged with the `tracing` feature
+        pub fn on_warning<F: Fn(&ParseWarning) + Send + Sync + 'static>(mut self, on_warning: F) -> ImageStream<S> {
+            self.parsing.set_on_warning(Arc::new(on_warning));
+            self.parser = parser(&self.parsing, self.status);
+            self
+        }
+
+        /// Returns the number of malformed entries skipped so far in [`ParseMode::Lenient`]
+        pub fn skipped(&self) -> usize {
+            self.parser.skipped()
+        }
+    }
+
+    fn parser(parsing: &Parsing, status: u16) -> Parser {
+        let mut parser = Parser::new(parsing.clone());
+        parser.http_status(status);
+        parser
+    }
+
+    impl<S, B, E> Stream for ImageStream<S>
+    where
+        S: Stream<Item = Result<B, E>> + Unpin,
+        B: AsRef<[u8]>,
+        E: Display
+    {
+        type Item = Result<String, DogAPIError>;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<String, DogAPIError>>> {
+            loop {
+                if let Some(item) = self.parser.pop() {
+                    if item.is_err() {
+                        self.finished = true;
+                    }
+                    return Poll::Ready(Some(item))
+                }
+                if self.finished {
+                    return Poll::Ready(None)
+                }
+                match Pin::new(&mut self.chunks).poll_next(cx) {
+                    Poll::Ready(Some(Ok(chunk))) => {
+                        let chunk = chunk.as_ref();
+                        self.bytes += chunk.len() as u64;
+                        if self.bytes > self.limit {
+                            self.finished = true;
+                            return Poll::Ready(Some(Err(DogAPIError::ResponseTooLarge(self.limit))))
+                        }
+                        self.parser.feed(chunk)
+                    },
+                    Poll::Ready(Some(Err(err))) => {
+                        self.finished = true;
+                        return Poll::Ready(Some(Err(DogAPIError::Error(format!("Something went wrong while reading bytes: {}", err)))))
+                    },
+                    Poll::Ready(None) => {
+                        self.finished = true;
+                        self.parser.finish();
+                        self.parsing.skipped("image_stream", self.parser.skipped());
+                    },
+                    Poll::Pending => return Poll::Pending
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::ParseMode;
+    #[cfg(feature = "stream")]
+    use crate::parse::ParseWarning;
+
+    const BODY: &str = r#"{"message": ["https://images.dog.ceo/breeds/hound-afghan/n1.jpg", "https://images.dog.ceo/breeds/hound-afghan/n2.jpg"], "status": "success"}"#;
+
+    /// Feeds `chunks` to a parser and returns the urls, or the first error, and the skipped count
+    fn parse(chunks: &[&[u8]], mode: ParseMode) -> (Result<Vec<String>, DogAPIError>, usize) {
+        let mut parsing = Parsing::default();
+        parsing.mode = mode;
+        let mut parser = Parser::new(parsing);
+        parser.http_status(200);
+        for chunk in chunks {
+            parser.feed(chunk);
+        }
+        parser.finish();
+        let mut urls = vec![];
+        while let Some(item) = parser.pop() {
+            match item {
+                Ok(url) => urls.push(url),
+                Err(err) => return (Err(err), parser.skipped())
+            }
+        }
+        (Ok(urls), parser.skipped())
+    }
+
+    fn urls(body: &str, mode: ParseMode) -> Result<Vec<String>, DogAPIError> {
+        parse(&[body.as_bytes()], mode).0
+    }
+
+    #[test]
+    fn parses_the_message_array() {
+        let (result, skipped) = parse(&[BODY.as_bytes()], ParseMode::Strict);
+        assert_eq!(result.unwrap(), vec![
+            "https://images.dog.ceo/breeds/hound-afghan/n1.jpg",
+            "https://images.dog.ceo/breeds/hound-afghan/n2.jpg"
+        ]);
+        assert_eq!(skipped, 0);
+        assert_eq!(urls(r#"{"status":"success","message":[]}"#, ParseMode::Strict).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn chunk_boundaries_do_not_matter() {
+        let expected = urls(BODY, ParseMode::Strict).unwrap();
+        let bytes = BODY.as_bytes();
+        for split in 0..=bytes.len() {
+            assert_eq!(parse(&[&bytes[..split], &bytes[split..]], ParseMode::Strict).0.unwrap(), expected, "split at {}", split);
+        }
+        let single: Vec<&[u8]> = bytes.chunks(1).collect();
+        assert_eq!(parse(&single, ParseMode::Strict).0.unwrap(), expected);
+    }
+
+    #[test]
+    fn decodes_escapes() {
+        let body = r#"{"message": ["a\/b\"c\\dé🐶"], "status": "success"}"#;
+        assert_eq!(urls(body, ParseMode::Strict).unwrap(), vec!["a/b\"c\\dé🐶"]);
+        let bytes = body.as_bytes();
+        for split in 0..=bytes.len() {
+            assert_eq!(parse(&[&bytes[..split], &bytes[split..]], ParseMode::Strict).0.unwrap(), vec!["a/b\"c\\dé🐶"]);
+        }
+        assert!(urls(r#"{"message": ["\x"], "status": "success"}"#, ParseMode::Lenient).is_err());
+    }
+
+    #[test]
+    fn skips_nested_values_outside_the_message() {
+        let body = r#"{"meta": {"message": ["not an image"], "list": [1, {"a": [true, null]}, "x"]}, "message": ["a"], "code": 200, "status": "success"}"#;
+        assert_eq!(urls(body, ParseMode::Strict).unwrap(), vec!["a"]);
+    }
+
+    #[test]
+    fn lenient_mode_skips_malformed_entries() {
+        let body = r#"{"message": [1, "a", {"b": "c"}, ["d"], null, "e"], "status": "success"}"#;
+        let (result, skipped) = parse(&[body.as_bytes()], ParseMode::Lenient);
+        assert_eq!(result.unwrap(), vec!["a", "e"]);
+        assert_eq!(skipped, 4);
+    }
+
+    #[test]
+    fn strict_mode_fails_on_malformed_entries() {
+        let body = r#"{"message": ["a", 1, "b"], "status": "success"}"#;
+        let err = urls(body, ParseMode::Strict).unwrap_err();
+        assert_eq!(err.to_string(), "Something went wrong while reading json: message[1] is not a string");
+    }
+
+    #[test]
+    fn rejects_misplaced_separators() {
+        let bodies = [
+            r#"{"message": [,,"a",,], "status": "success"}"#,
+            r#"{"message": ["a",], "status": "success"}"#,
+            r#"{"message": ["a" "b"], "status": "success"}"#,
+            r#"{"message" "status" ["a"], "status": "success"}"#,
+            r#"{"message": ["a"] "status": "success"}"#,
+            r#"{"message": ["a"], "status": "success",}"#,
+            r#"{"message":: ["a"], "status": "success"}"#,
+            r#"{,"message": ["a"], "status": "success"}"#,
+            r#"{"message": ["a"], "status"}"#,
+            r#"{"message": ["a"}, "status": "success"}"#,
+            r#"{"meta": {"x" 1}, "message": ["a"], "status": "success"}"#
+        ];
+        for body in bodies {
+            for mode in [ParseMode::Strict, ParseMode::Lenient] {
+                let (result, _) = parse(&[body.as_bytes()], mode);
+                assert!(result.is_err(), "{} was accepted", body);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_documents_other_than_an_object() {
+        let (result, _) = parse(&[br#"["a" "b"] "status""#], ParseMode::Lenient);
+        assert_eq!(result.unwrap_err().to_string(), "Something went wrong while reading json: expected an object");
+        assert!(urls(r#"["a", "b"]"#, ParseMode::Lenient).is_err());
+        assert!(urls(r#""a""#, ParseMode::Lenient).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_literals() {
+        assert!(urls(r#"{"message": ["a"], "code": 4x4, "status": "success"}"#, ParseMode::Lenient).is_err());
+        assert!(urls(r#"{"message": [nul], "status": "success"}"#, ParseMode::Lenient).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_and_trailing_input() {
+        assert!(urls(&BODY[..BODY.len() - 1], ParseMode::Lenient).is_err());
+        assert!(urls(r#"{"message": ["a"#, ParseMode::Lenient).is_err());
+        assert!(urls(&format!("{} {{}}", BODY), ParseMode::Lenient).is_err());
+        assert!(urls(&format!("{}\n", BODY), ParseMode::Lenient).is_ok());
+        assert!(urls("", ParseMode::Lenient).is_err());
+    }
+
+    #[test]
+    fn requires_the_message_array() {
+        let err = urls(r#"{"status": "success"}"#, ParseMode::Lenient).unwrap_err();
+        assert_eq!(err.to_string(), "Something went wrong while reading json: missing message array");
+        assert!(urls(r#"{"message": "a", "status": "success"}"#, ParseMode::Lenient).is_err());
+    }
+
+    #[test]
+    fn reports_api_errors() {
+        let mut parser = Parser::new(Parsing::default());
+        parser.http_status(404);
+        parser.feed(br#"{"status": "error", "message": "Breed not found (master breed does not exist)", "code": 404}"#);
+        parser.finish();
+        let err = parser.pop().unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "Breed not found (master breed does not exist)");
+        assert_eq!(err.code(), Some(404));
+        assert_eq!(err.status(), Some(404));
+        assert!(parser.pop().is_none());
+    }
+
+    #[cfg(feature = "stream")]
+    fn collect_stream(stream: ImageStream<Chunks>) -> Vec<Result<String, DogAPIError>> {
+        use futures_core::Stream;
+        use std::pin::Pin;
+        use std::task::{Context, Poll, Waker};
+        let mut stream = stream;
+        let mut context = Context::from_waker(Waker::noop());
+        let mut items = vec![];
+        while let Poll::Ready(Some(item)) = Pin::new(&mut stream).poll_next(&mut context) {
+            items.push(item);
+        }
+        items
+    }
+
+    #[cfg(feature = "stream")]
+    struct Chunks(VecDeque<Vec<u8>>);
+
+    #[cfg(feature = "stream")]
+    impl futures_core::Stream for Chunks {
+        type Item = Result<Vec<u8>, String>;
+
+        fn poll_next(mut self: std::pin::Pin<&mut Self>, _: &mut std::task::Context<'_>) -> std::task::Poll<Option<Self::Item>> {
+            std::task::Poll::Ready(self.0.pop_front().map(Ok))
+        }
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn image_stream_enforces_the_size_limit() {
+        let chunks = || Chunks(BODY.as_bytes().chunks(16).map(|chunk| chunk.to_vec()).collect());
+        let items = collect_stream(ImageStream::new(chunks(), 200));
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|item| item.is_ok()));
+        let items = collect_stream(ImageStream::new(chunks(), 200).max_size(BODY.len() as u64));
+        assert_eq!(items.len(), 2);
+        let items = collect_stream(ImageStream::new(chunks(), 200).max_size(64));
+        assert!(matches!(items.last(), Some(Err(DogAPIError::ResponseTooLarge(64)))));
+        assert!(items.len() <= 2);
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn image_stream_reports_status_and_warnings() {
+        use std::sync::{Arc, Mutex};
+        let body = br#"{"status": "error", "message": "Breed not found (master breed does not exist)", "code": 404}"#;
+        let items = collect_stream(ImageStream::new(Chunks(VecDeque::from([body.to_vec()])), 404));
+        let err = items[0].as_ref().unwrap_err();
+        assert_eq!((err.code(), err.status()), (Some(404), Some(404)));
+
+        let warnings = Arc::new(Mutex::new(vec![]));
+        let sink = warnings.clone();
+        let body = br#"{"message": [1, "a", null], "status": "success"}"#;
+        let stream = ImageStream::new(Chunks(VecDeque::from([body.to_vec()])), 200)
+            .on_warning(move |warning| sink.lock().unwrap().push(warning.clone()))
+            .parse_mode(ParseMode::Lenient);
+        assert_eq!(collect_stream(stream).len(), 1);
+        assert_eq!(*warnings.lock().unwrap(), vec![ParseWarning { endpoint: "image_stream".to_string(), skipped: 2 }]);
+    }
+}
@@ -0,0 +1,77 @@
+/*
+ * Copyright 2022 XXIV
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! Fixtures shared by the unit tests
+use std::collections::HashMap;
+use std::fs;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use crate::client::Client;
+use crate::server::Server;
+
+/// Directory under the system temp directory, removed on drop
+pub(crate) struct TempDir {
+    path: PathBuf
+}
+
+impl TempDir {
+    pub(crate) fn new() -> TempDir {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!("dogapi-test-{}-{}", std::process::id(), NEXT.fetch_add(1, Ordering::SeqCst)));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes a file, creating its parent directories
+    pub(crate) fn write(&self, path: &str, bytes: &[u8]) {
+        let file = self.path.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, bytes).unwrap();
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Serves a directory with a `DirectoryBackend` on a free local port, returns the API url
+pub(crate) fn serve(root: &Path) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Server::from_dir(root).unwrap();
+    thread::spawn(move || server.run(listener));
+    format!("http://{}/api", addr)
+}
+
+/// Client of an API url
+pub(crate) fn client(base_url: &str) -> Client {
+    Client::builder().base_url(base_url).build().unwrap()
+}
+
+/// Breeds list of `(breed, sub-breeds)` entries, as returned by `Client::breeds_list`
+pub(crate) fn catalog(entries: &[(&str, Option<&[&str]>)]) -> HashMap<String, Option<Vec<String>>> {
+    entries.iter()
+        .map(|(breed, sub_breeds)| (breed.to_string(), sub_breeds.map(|sub_breeds| sub_breeds.iter().map(|sub_breed| sub_breed.to_string()).collect())))
+        .collect()
+}